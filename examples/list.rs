@@ -5,16 +5,16 @@ fn main() {
     let path = args.nth(1).unwrap_or_default();
     let mut key = None;
     if let Some(hash) = args.next() {
-        match hex::decode(hash.trim_start_matches("0x")) {
-            Ok(bytes) => key = Some(bytes),
+        match hash.parse::<unpak::AesKey>() {
+            Ok(parsed) => key = Some(parsed),
             Err(e) => {
-                eprintln!("hex: {e}");
+                eprintln!("{e}");
                 std::io::stdin().read_line(&mut String::new()).unwrap();
                 return;
             }
         }
     }
-    match unpak::Pak::new_any(path, key.as_deref()) {
+    match unpak::Pak::new_any(path, key.map(unpak::KeyChain::from).unwrap_or_default()) {
         Ok(pak) => pak
             .entries()
             .into_par_iter()
@@ -0,0 +1,39 @@
+fn main() {
+    let mut args = std::env::args();
+    let dir = args.nth(1).unwrap_or_default();
+    let out = args
+        .next()
+        .unwrap_or_else(|| dir.trim_end_matches(['/', '\\']).to_string() + ".pak");
+    match pack(&dir, &out) {
+        Ok(_) => println!("packed successfully"),
+        Err(e) => eprintln!("{e}"),
+    }
+    std::io::stdin().read_line(&mut String::new()).unwrap();
+}
+
+fn pack(dir: &str, out: &str) -> Result<(), unpak::Error> {
+    let mut pak = unpak::PakWriter::new(
+        std::io::BufWriter::new(std::fs::File::create(out)?),
+        unpak::Version::Fnv64BugFix,
+        "../../../",
+    );
+    let mut dirs = vec![std::path::PathBuf::from(dir)];
+    while let Some(next) = dirs.pop() {
+        for file in std::fs::read_dir(next)? {
+            let path = file?.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let entry = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            pak.write_entry(&entry, &std::fs::read(&path)?)?;
+            println!("{entry}");
+        }
+    }
+    pak.finish()?;
+    Ok(())
+}
@@ -1,35 +1,36 @@
-use rayon::prelude::*;
-
 fn main() {
     let mut args = std::env::args();
     let path = args.nth(1).unwrap_or_default();
     let mut key = None;
     if let Some(hash) = args.next() {
-        match hex::decode(hash.trim_start_matches("0x")) {
-            Ok(bytes) => key = Some(bytes),
+        match hash.parse::<unpak::AesKey>() {
+            Ok(parsed) => key = Some(parsed),
             Err(e) => {
-                eprintln!("hex: {e}");
+                eprintln!("{e}");
                 std::io::stdin().read_line(&mut String::new()).unwrap();
                 return;
             }
         }
     }
-    match unpack(&path, key.as_deref()) {
+    match unpack(&path, key.map(unpak::KeyChain::from).unwrap_or_default()) {
         Ok(_) => println!("unpacked successfully"),
         Err(e) => eprintln!("{e}"),
     }
     std::io::stdin().read_line(&mut String::new()).unwrap();
 }
 
-fn unpack(path: &str, key: Option<&[u8]>) -> Result<(), unpak::Error> {
-    let pak = unpak::Pak::new_any(path, key)?;
-    pak.entries()
-        .into_par_iter()
-        .try_for_each(|entry| -> Result<(), unpak::Error> {
-            std::fs::create_dir_all(std::path::Path::new(&entry).parent().unwrap())?;
-            pak.read_to_file(&entry, &entry)?;
-            println!("{entry}");
-            Ok(())
-        })?;
+fn unpack(path: &str, keys: unpak::KeyChain) -> Result<(), unpak::Error> {
+    let pak = unpak::Pak::new_any(path, keys)?;
+    let extracted = pak.extract_all(
+        ".",
+        unpak::ExtractOptions::new()
+            .with_progress(|progress| println!("{}/{} entries", progress.entries, progress.total)),
+    )?;
+    for (entry, e) in extracted.errors {
+        eprintln!("{entry}: {e}");
+    }
+    for entry in extracted.rejected {
+        eprintln!("{entry}: path escapes the output directory");
+    }
     Ok(())
 }
@@ -0,0 +1,50 @@
+use super::Compression;
+use std::{io, sync::Arc};
+
+/// decompresses blocks of a compression method the crate doesn't handle itself
+pub trait Decompressor: Send + Sync {
+    /// decompresses a block into the writer - uncompressed is the size it should decompress to
+    fn decompress(
+        &self,
+        data: &[u8],
+        uncompressed: usize,
+        buf: &mut dyn io::Write,
+    ) -> Result<(), super::Error>;
+}
+
+impl<F> Decompressor for F
+where
+    F: Fn(&[u8], usize, &mut dyn io::Write) -> Result<(), super::Error> + Send + Sync,
+{
+    fn decompress(
+        &self,
+        data: &[u8],
+        uncompressed: usize,
+        buf: &mut dyn io::Write,
+    ) -> Result<(), super::Error> {
+        self(data, uncompressed, buf)
+    }
+}
+
+/// registered decompressors keyed by the lowercase compression method name
+#[derive(Clone, Default)]
+pub(crate) struct Decompressors(hashbrown::HashMap<String, Arc<dyn Decompressor>>);
+
+impl Decompressors {
+    pub fn insert(&mut self, name: &str, decompressor: Arc<dyn Decompressor>) {
+        self.0.insert(name.to_ascii_lowercase(), decompressor);
+    }
+
+    pub fn get(&self, compression: &Compression) -> Option<&dyn Decompressor> {
+        // unreal doesn't care about the case of compression names
+        self.0
+            .get(&compression.to_string().to_ascii_lowercase())
+            .map(Arc::as_ref)
+    }
+}
+
+impl std::fmt::Debug for Decompressors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
@@ -1,8 +1,11 @@
-use super::{ext::ReadExt, Compression, Version};
-use byteorder::{ReadBytesExt, LE};
-use std::io;
+use super::{decompressor::Decompressors, ext::ReadExt, Compression, Version};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use std::{borrow::Cow, io, ops::Range};
 
-#[derive(Debug)]
+/// the size of the chunks stored data is read in
+const CHUNK: u64 = 0x10000;
+
+#[derive(Clone, Debug)]
 struct Block {
     start: u64,
     end: u64,
@@ -17,7 +20,7 @@ impl Block {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Entry {
     offset: u64,
     compressed: u64,
@@ -25,10 +28,47 @@ pub struct Entry {
     compression: Option<usize>,
     blocks: Option<Vec<Block>>,
     encrypted: bool,
+    deleted: bool,
     block_uncompressed: u64,
+    // encoded records don't have a hash
+    hash: Option<[u8; 20]>,
+}
+
+/// read-only metadata about an entry
+#[derive(Clone, Debug)]
+pub struct EntryInfo {
+    /// offset of the entry record in the pak
+    pub offset: u64,
+    /// size of the stored data
+    pub compressed: u64,
+    /// size of the data once decompressed
+    pub uncompressed: u64,
+    /// compression method used by the entry
+    pub compression: Compression,
+    /// number of compression blocks
+    pub blocks: usize,
+    /// whether the stored data is encrypted
+    pub encrypted: bool,
+    /// whether this is a delete record removing the entry from lower priority paks
+    pub deleted: bool,
+    /// uncompressed size of each compression block
+    pub block_uncompressed: u64,
 }
 
 impl Entry {
+    pub fn info(&self, compression: &[Compression]) -> EntryInfo {
+        EntryInfo {
+            offset: self.offset,
+            compressed: self.compressed,
+            uncompressed: self.uncompressed,
+            compression: self.compression(compression).cloned().unwrap_or_default(),
+            blocks: self.blocks.as_ref().map_or(0, Vec::len),
+            encrypted: self.encrypted,
+            deleted: self.deleted,
+            block_uncompressed: self.block_uncompressed,
+        }
+    }
+
     pub fn new<R: io::Read + io::Seek>(
         reader: &mut R,
         version: super::Version,
@@ -48,13 +88,21 @@ impl Entry {
         if version == Version::Initial {
             reader.read_u64::<LE>()?;
         }
-        // hash
-        reader.read_guid()?;
+        let hash = reader.read_guid()?;
         let blocks = match version >= Version::CompressionEncryption && compression.is_some() {
-            true => Some(reader.read_array(Block::new)?),
+            true => Some(ReadExt::read_array(reader, Block::new)?),
             false => None,
         };
-        let encrypted = version >= Version::CompressionEncryption && reader.read_bool()?;
+        let flags = match version >= Version::CompressionEncryption {
+            true => reader.read_u8()?,
+            false => 0,
+        };
+        // flags were a boolean before delete records
+        let (encrypted, deleted) = match flags {
+            0 | 1 => (flags == 1, false),
+            2 | 3 if version >= Version::DeleteRecords => (flags == 3, true),
+            err => return Err(super::Error::Bool(err)),
+        };
         let block_uncompressed = match version >= Version::CompressionEncryption {
             true => reader.read_u32::<LE>()? as u64,
             false => uncompressed,
@@ -66,7 +114,9 @@ impl Entry {
             compression,
             blocks,
             encrypted,
+            deleted,
             block_uncompressed,
+            hash: Some(hash),
         })
     }
 
@@ -128,144 +178,535 @@ impl Entry {
             compression,
             blocks,
             encrypted,
+            // delete records are never encoded
+            deleted: false,
             block_uncompressed,
+            hash: None,
         })
     }
 
-    pub fn read<W: io::Write>(
+    /// size of the record for the given version
+    pub fn record_size(version: Version, compressed: bool, block_count: usize) -> u64 {
+        // offset + compressed + uncompressed: u64 + hash: [u8; 20]
+        let mut size = 8 + 8 + 8 + 20;
+        size += match version == Version::FNameBasedCompression {
+            // compression: u8
+            true => 1,
+            // compression: u32
+            false => 4,
+        };
+        if version == Version::Initial {
+            // timestamp: u64
+            size += 8;
+        }
+        if version >= Version::CompressionEncryption {
+            if compressed {
+                // blocks: [(u64, u64)]
+                size += 4 + 16 * block_count as u64;
+            }
+            // flags: u8 + block uncompressed: u32
+            size += 1 + 4;
+        }
+        size
+    }
+
+    pub fn write<W: io::Write>(
         &self,
-        path: impl AsRef<std::path::Path>,
-        version: super::Version,
-        compression: &[super::Compression],
-        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
-        buf: &mut W,
+        writer: &mut W,
+        version: Version,
     ) -> Result<(), super::Error> {
-        use io::Seek;
-        let mut reader = std::fs::File::open(&path)?;
+        writer.write_u64::<LE>(self.offset)?;
+        writer.write_u64::<LE>(self.compressed)?;
+        writer.write_u64::<LE>(self.uncompressed)?;
+        let compression = self.compression.map_or(0, |i| i as u32 + 1);
+        match version == Version::FNameBasedCompression {
+            true => writer.write_u8(compression as u8)?,
+            false => writer.write_u32::<LE>(compression)?,
+        }
+        // timestamp
+        if version == Version::Initial {
+            writer.write_u64::<LE>(0)?;
+        }
+        writer.write_all(&self.hash.unwrap_or_default())?;
+        if version >= Version::CompressionEncryption {
+            if self.compression.is_some() {
+                let blocks = self.blocks.as_deref().unwrap_or_default();
+                writer.write_u32::<LE>(blocks.len() as u32)?;
+                for block in blocks {
+                    writer.write_u64::<LE>(block.start)?;
+                    writer.write_u64::<LE>(block.end)?;
+                }
+            }
+            writer.write_u8(self.encrypted as u8 | (self.deleted as u8) << 1)?;
+            writer.write_u32::<LE>(self.block_uncompressed as u32)?;
+        }
+        Ok(())
+    }
+
+    /// a record marking the entry as deleted
+    pub fn delete_record() -> Self {
+        Self {
+            offset: 0,
+            compressed: 0,
+            uncompressed: 0,
+            compression: None,
+            blocks: None,
+            encrypted: false,
+            deleted: true,
+            block_uncompressed: 0,
+            hash: Some([0; 20]),
+        }
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    /// whether the record can be stored in the encoded form
+    pub fn encodable(&self) -> bool {
+        !self.deleted
+            && self.blocks.as_ref().map_or(0, Vec::len) <= 0xffff
+            && self.compression.map_or(0, |i| i + 1) <= 0x3F
+    }
+
+    pub fn write_encoded<W: io::Write>(&self, writer: &mut W) -> Result<(), super::Error> {
+        let block_count = self.blocks.as_ref().map_or(0, Vec::len);
+        // the block count only has 16 bits
+        if block_count > 0xffff {
+            return Err(super::Error::Other(format!(
+                "{block_count} blocks is too many to encode"
+            )));
+        }
+        let compression = self.compression.map_or(0, |i| i as u32 + 1);
+        if compression > 0x3F {
+            return Err(super::Error::Other(format!(
+                "compression slot {compression} is too large to encode"
+            )));
+        }
+        // block sizes which aren't a multiple of 0x800 are written separately
+        let block_size = match self.block_uncompressed >> 11 {
+            size if size << 11 == self.block_uncompressed && size < 0x3F => size as u32,
+            _ => 0x3F,
+        };
+        let small = |value: u64| value <= u32::MAX as u64;
+        let bitfield = (small(self.offset) as u32) << 31
+            | (small(self.uncompressed) as u32) << 30
+            | (small(self.compressed) as u32) << 29
+            | compression << 23
+            | (self.encrypted as u32) << 22
+            | (block_count as u32) << 6
+            | block_size;
+        writer.write_u32::<LE>(bitfield)?;
+        if block_size == 0x3F {
+            writer.write_u32::<LE>(self.block_uncompressed as u32)?;
+        }
+        let mut value = |value: u64| -> Result<(), super::Error> {
+            match small(value) {
+                true => writer.write_u32::<LE>(value as u32)?,
+                false => writer.write_u64::<LE>(value)?,
+            }
+            Ok(())
+        };
+        value(self.offset)?;
+        value(self.uncompressed)?;
+        if self.compression.is_some() {
+            value(self.compressed)?;
+        }
+        // a single unencrypted block can be inferred from the compressed size
+        if let Some(blocks) = self
+            .blocks
+            .as_ref()
+            .filter(|blocks| blocks.len() > 1 || self.encrypted)
+        {
+            for block in blocks {
+                writer.write_u32::<LE>((block.end - block.start) as u32)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// writes the record and data at the current position
+    pub fn write_data<W: io::Write + io::Seek>(
+        writer: &mut W,
+        version: Version,
+        compression: Option<(usize, &Compression)>,
+        block_size: u32,
+        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Enc>,
+        data: &[u8],
+    ) -> Result<Self, super::Error> {
+        if block_size == 0 {
+            return Err(super::Error::Other("block size can't be zero".to_string()));
+        }
+        let offset = writer.stream_position()?;
+        let mut chunks = Vec::new();
+        if let Some((_, method)) = compression.filter(|_| !data.is_empty()) {
+            match version >= Version::CompressionEncryption {
+                true => {
+                    for chunk in data.chunks(block_size as usize) {
+                        chunks.push(compress(method, chunk)?);
+                    }
+                }
+                // older versions compress the whole entry as one block
+                false => chunks.push(compress(method, data)?),
+            }
+        }
+        // like unrealpak store the data as is if compression doesn't help
+        let compression = match chunks.iter().map(Vec::len).sum::<usize>() {
+            size if chunks.is_empty() || size >= data.len() => {
+                chunks = vec![data.to_vec()];
+                None
+            }
+            _ => compression,
+        };
+        #[cfg(feature = "encryption")]
+        let encrypted = key.is_some();
+        #[cfg(not(feature = "encryption"))]
+        let encrypted = false;
+        // each compressed block is aligned to the aes block size when encrypted
+        let stored = |size: usize| match encrypted && compression.is_some() {
+            true => (size as u64 + 15) & !15,
+            false => size as u64,
+        };
+        let blocks = match version >= Version::CompressionEncryption && compression.is_some() {
+            true => {
+                let mut start = Entry::record_size(version, true, chunks.len());
+                if version < Version::RelativeChunkOffsets {
+                    start += offset;
+                }
+                let mut blocks = Vec::with_capacity(chunks.len());
+                for chunk in chunks.iter() {
+                    blocks.push(Block {
+                        start,
+                        end: start + chunk.len() as u64,
+                    });
+                    start += stored(chunk.len());
+                }
+                Some(blocks)
+            }
+            false => None,
+        };
+        let mut entry = Self {
+            // the record in front of the data doesn't store the offset
+            offset: 0,
+            compressed: chunks.iter().map(|chunk| stored(chunk.len())).sum(),
+            uncompressed: data.len() as u64,
+            compression: compression.map(|(slot, _)| slot),
+            blocks,
+            encrypted,
+            deleted: false,
+            block_uncompressed: match version >= Version::CompressionEncryption {
+                true if compression.is_some() => (block_size as u64).min(data.len() as u64),
+                true => 0,
+                false => data.len() as u64,
+            },
+            hash: None,
+        };
+        for chunk in chunks.iter_mut() {
+            chunk.resize(stored(chunk.len()) as usize, 0);
+        }
+        // the hash is of the data as it's stored before encryption
+        entry.hash = Some(super::hash::sha1(&chunks.concat()));
+        entry.write(writer, version)?;
+        #[allow(unused_mut)]
+        for mut chunk in chunks {
+            #[cfg(feature = "encryption")]
+            if let Some(key) = key {
+                super::encrypt(key, &mut chunk);
+            }
+            writer.write_all(&chunk)?;
+        }
+        entry.offset = offset;
+        Ok(entry)
+    }
+
+    /// reads the record in front of the data, the data offset and the decrypted data
+    ///
+    /// the data is borrowed from the pak if it's in memory and isn't encrypted
+    fn read_stored<'a, R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        pak: Option<&'a [u8]>,
+        version: Version,
+        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
+    ) -> Result<(Entry, u64, Cow<'a, [u8]>), super::Error> {
         reader.seek(io::SeekFrom::Start(self.offset))?;
-        Entry::new(&mut reader, version)?;
+        let record = Entry::new(reader, version)?;
         let data_offset = reader.stream_position()?;
-        #[allow(unused_mut)]
-        let mut data = reader.read_len(match self.encrypted {
+        let size = match self.encrypted {
             // add alignment (aes block size: 16) then zero out alignment bits
             true => (self.compressed + 15) & !15,
             false => self.compressed,
-        } as usize)?;
+        } as usize;
+        let data = match pak {
+            Some(pak) => match pak.get(data_offset as usize..data_offset as usize + size) {
+                Some(data) => Cow::Borrowed(data),
+                None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            },
+            None => Cow::Owned(reader.read_len(size)?),
+        };
         if self.encrypted {
             #[cfg(feature = "encryption")]
             {
+                let mut data = data.into_owned();
                 super::decrypt(key, &mut data)?;
                 data.truncate(self.compressed as usize);
+                return Ok((record, data_offset, Cow::Owned(data)));
             }
             #[cfg(not(feature = "encryption"))]
             return Err(super::Error::Encryption);
         }
-        let blocks = match &self.blocks {
-            Some(blocks) => blocks
-                .iter()
-                .map(|block| match version >= Version::RelativeChunkOffsets {
-                    true => {
-                        (block.start - (data_offset - self.offset)) as usize
-                            ..(block.end - (data_offset - self.offset)) as usize
-                    }
-                    false => {
-                        (block.start - data_offset) as usize..(block.end - data_offset) as usize
-                    }
+        Ok((record, data_offset, data))
+    }
+
+    /// checks the stored data against the recorded hash
+    pub fn verify<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+        version: Version,
+        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
+    ) -> Result<bool, super::Error> {
+        let (record, _, data) = self.read_stored(
+            reader,
+            pak,
+            version,
+            #[cfg(feature = "encryption")]
+            key,
+        )?;
+        // encoded entries rely on the record in front of the data
+        Ok(self.hash.or(record.hash) == Some(super::hash::sha1(&data)))
+    }
+
+    /// the compression method from the pak's compression table
+    pub fn compression<'a>(&self, compression: &'a [Compression]) -> Option<&'a Compression> {
+        self.compression.and_then(|i| compression.get(i))
+    }
+
+    /// skips the record in front of the data to get the data offset
+    pub fn data_offset<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        version: Version,
+    ) -> Result<u64, super::Error> {
+        reader.seek(io::SeekFrom::Start(self.offset))?;
+        Entry::new(reader, version)?;
+        Ok(reader.stream_position()?)
+    }
+
+    /// where the data is in the pak if it's stored without compression or encryption
+    pub fn stored_range<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        version: Version,
+        compression: &[Compression],
+    ) -> Result<Option<Range<u64>>, super::Error> {
+        if self.encrypted
+            || !matches!(
+                self.compression(compression),
+                None | Some(Compression::None)
+            )
+        {
+            return Ok(None);
+        }
+        let data_offset = self.data_offset(reader, version)?;
+        Ok(Some(data_offset..data_offset + self.compressed))
+    }
+
+    /// the range of each block within the stored data and the decompressed data
+    pub fn block_ranges(
+        &self,
+        version: Version,
+        data_offset: u64,
+        compression: Option<&Compression>,
+    ) -> Vec<(Range<usize>, Range<u64>)> {
+        match (&self.blocks, compression) {
+            // stored data can be split anywhere so is read in chunks
+            (_, None | Some(Compression::None)) => (0..self.compressed)
+                .step_by(CHUNK as usize)
+                .map(|start| {
+                    let end = (start + CHUNK).min(self.compressed);
+                    (start as usize..end as usize, start..end)
                 })
                 .collect(),
-            None => vec![0..data.len()],
-        };
-        match self.compression.and_then(|i| compression.get(i)) {
-            None | Some(Compression::None) => {
-                for block in blocks {
-                    buf.write_all(&data[block])?
-                }
+            (Some(blocks), _) => {
+                let base = match version >= Version::RelativeChunkOffsets {
+                    true => data_offset - self.offset,
+                    false => data_offset,
+                };
+                blocks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, block)| {
+                        let uncompressed = match blocks.len() == 1 {
+                            true => 0..self.uncompressed,
+                            false => {
+                                let start = i as u64 * self.block_uncompressed;
+                                start..(start + self.block_uncompressed).min(self.uncompressed)
+                            }
+                        };
+                        (
+                            (block.start - base) as usize..(block.end - base) as usize,
+                            uncompressed,
+                        )
+                    })
+                    .collect()
             }
-            #[cfg(feature = "compression")]
-            Some(Compression::Zlib) => {
-                for block in blocks {
-                    io::copy(&mut flate2::read::ZlibDecoder::new(&data[block]), buf)?;
-                }
-            }
-            #[cfg(feature = "compression")]
-            Some(Compression::Gzip) => {
-                for block in blocks {
-                    io::copy(&mut flate2::read::GzDecoder::new(&data[block]), buf)?;
-                }
-            }
-            #[cfg(feature = "oodle")]
-            Some(Compression::Oodle) => {
-                let block_count = blocks.len();
-                for (i, block) in blocks.into_iter().enumerate() {
-                    let data = &data[block];
-                    let mut scratch = vec![
-                        0;
-                        match block_count == 1 {
-                            true => self.uncompressed,
-                            false => self
-                                .block_uncompressed
-                                .min(self.uncompressed - i as u64 * self.block_uncompressed),
-                        } as usize
-                    ];
-                    if unsafe {
-                        OodleLZ_Decompress(
-                            data.as_ptr(),
-                            data.len(),
-                            scratch.as_mut_ptr(),
-                            scratch.len(),
-                            1,
-                            1,
-                            0,
-                            0,
-                            0,
-                            0,
-                            0,
-                            std::ptr::null_mut(),
-                            0,
-                            3,
-                        ) == 0
-                    } {
-                        return Err(super::Error::OodleLZ_Decompress);
-                    }
-                    buf.write_all(scratch.as_slice())?;
-                }
+            (None, _) => vec![(0..self.compressed as usize, 0..self.uncompressed)],
+        }
+    }
+
+    /// reads and decompresses a single block
+    pub fn read_block<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        data_offset: u64,
+        (stored, uncompressed): (Range<usize>, Range<u64>),
+        compression: Option<&Compression>,
+        decompressors: &Decompressors,
+        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
+    ) -> Result<Vec<u8>, super::Error> {
+        reader.seek(io::SeekFrom::Start(data_offset + stored.start as u64))?;
+        #[allow(unused_mut)]
+        let mut data = reader.read_len(match self.encrypted {
+            // blocks of encrypted entries are aligned
+            true => (stored.len() + 15) & !15,
+            false => stored.len(),
+        })?;
+        if self.encrypted {
+            #[cfg(feature = "encryption")]
+            {
+                super::decrypt(key, &mut data)?;
+                data.truncate(stored.len());
             }
-            #[cfg(all(feature = "compression", not(feature = "oodle")))]
-            Some(Compression::Oodle) => return Err(crate::Error::Oodle),
-            #[allow(unreachable_patterns)]
-            _ => return Err(super::Error::Compression),
+            #[cfg(not(feature = "encryption"))]
+            return Err(super::Error::Encryption);
+        }
+        let size = (uncompressed.end - uncompressed.start) as usize;
+        let mut buf = Vec::with_capacity(size);
+        decompress(compression, decompressors, &data, size, &mut buf)?;
+        Ok(buf)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn read<R: io::Read + io::Seek, W: io::Write>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+        version: super::Version,
+        compression: &[super::Compression],
+        decompressors: &Decompressors,
+        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
+        buf: &mut W,
+    ) -> Result<(), super::Error> {
+        let (_, data_offset, data) = self.read_stored(
+            reader,
+            pak,
+            version,
+            #[cfg(feature = "encryption")]
+            key,
+        )?;
+        let compression = self.compression(compression);
+        for (stored, uncompressed) in self.block_ranges(version, data_offset, compression) {
+            decompress(
+                compression,
+                decompressors,
+                &data[stored],
+                (uncompressed.end - uncompressed.start) as usize,
+                buf,
+            )?;
         }
         buf.flush()?;
         Ok(())
     }
 }
 
-#[cfg(feature = "oodle")]
-#[cfg_attr(target_os = "windows", link(name = "oo2core_win64", kind = "static"))]
-#[cfg_attr(target_os = "macos", link(name = "liboo2coremac64", kind = "static"))]
-#[cfg_attr(
-    all(target_os = "linux", target_arch = "x86_64"),
-    link(name = "liboo2corelinux64", kind = "static")
-)]
-#[cfg_attr(
-    all(target_os = "linux", target_arch = "arm"),
-    link(name = "liboo2corelinuxarm64", kind = "static")
-)]
-extern "C" {
-    fn OodleLZ_Decompress(
-        compBuf: *const u8,
-        compBufSize: usize,
-        rawBuf: *mut u8,
-        rawLen: usize,
-        fuzzSafe: u32,
-        checkCRC: u32,
-        verbosity: u32,
-        decBufBase: u64,
-        decBufSize: usize,
-        fpCallback: u64,
-        callbackUserData: u64,
-        decoderMemory: *mut u8,
-        decoderMemorySize: usize,
-        threadPhase: u32,
-    ) -> i32;
+/// decompresses a block into the writer
+#[cfg_attr(not(any(feature = "oodle", feature = "lz4")), allow(unused_variables))]
+fn decompress<W: io::Write>(
+    compression: Option<&Compression>,
+    decompressors: &Decompressors,
+    data: &[u8],
+    uncompressed: usize,
+    buf: &mut W,
+) -> Result<(), super::Error> {
+    // registered decompressors take priority over the built in ones
+    if let Some(decompressor) = compression.and_then(|compression| decompressors.get(compression)) {
+        return decompressor.decompress(data, uncompressed, buf);
+    }
+    match compression {
+        None | Some(Compression::None) => buf.write_all(data)?,
+        #[cfg(feature = "compression")]
+        Some(Compression::Zlib) => {
+            io::copy(&mut flate2::read::ZlibDecoder::new(data), buf)?;
+        }
+        #[cfg(feature = "compression")]
+        Some(Compression::Gzip) => {
+            io::copy(&mut flate2::read::GzDecoder::new(data), buf)?;
+        }
+        #[cfg(feature = "oodle")]
+        Some(Compression::Oodle) => {
+            super::oodle::decompress(super::oodle::OodleLZ_Decompress, data, uncompressed, buf)?
+        }
+        #[cfg(not(feature = "oodle"))]
+        Some(Compression::Oodle) => return Err(crate::Error::Oodle),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => {
+            zstd::stream::copy_decode(data, buf)?;
+        }
+        #[cfg(not(feature = "zstd"))]
+        Some(Compression::Zstd) => return Err(super::Error::Zstd),
+        #[cfg(feature = "lz4")]
+        Some(Compression::Lz4) => {
+            // unreal uses the raw block format without the size in front
+            match lz4_flex::block::decompress(data, uncompressed) {
+                Ok(data) => buf.write_all(&data)?,
+                Err(e) => return Err(super::Error::Other(format!("lz4: {e}"))),
+            }
+        }
+        #[cfg(not(feature = "lz4"))]
+        Some(Compression::Lz4) => return Err(super::Error::Lz4),
+        Some(Compression::Other(name)) => {
+            return Err(super::Error::UnsupportedCompression(name.clone()))
+        }
+        #[allow(unreachable_patterns)]
+        _ => return Err(super::Error::Compression),
+    }
+    Ok(())
+}
+
+fn compress(compression: &Compression, data: &[u8]) -> Result<Vec<u8>, super::Error> {
+    #[cfg(feature = "compression")]
+    use io::Write;
+    match compression {
+        Compression::None => Ok(data.to_vec()),
+        #[cfg(feature = "compression")]
+        Compression::Zlib => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        #[cfg(feature = "compression")]
+        Compression::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        #[cfg(feature = "compression")]
+        Compression::Oodle => Err(super::Error::Other(
+            "oodle compression isn't supported when writing".to_string(),
+        )),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(zstd::bulk::compress(data, 0)?),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => Err(super::Error::Zstd),
+        #[cfg(feature = "lz4")]
+        Compression::Lz4 => Ok(lz4_flex::block::compress(data)),
+        #[cfg(not(feature = "lz4"))]
+        Compression::Lz4 => Err(super::Error::Lz4),
+        Compression::Other(name) => Err(super::Error::UnsupportedCompression(name.clone())),
+        #[allow(unreachable_patterns)]
+        _ => Err(super::Error::Compression),
+    }
 }
@@ -9,6 +9,9 @@ pub enum Error {
     /// OodleLZ_Decompress returned 0
     #[error("failed to decompress oodle chunk")]
     OodleLZ_Decompress,
+    /// the oodle shared library couldn't be loaded
+    #[error("failed to load oodle from {0}")]
+    OodleLibrary(String),
 
     // standard library errors
     /// std::io error
@@ -31,9 +34,20 @@ pub enum Error {
     /// re-enable the compression feature to read compressed paks
     #[error("re-enable the compression feature to read compressed paks")]
     Compression,
-    /// enable the oodle feature to decompress oodle paks
-    #[error("enable the oodle feature to decompress oodle paks")]
+    /// enable the oodle feature or register a loaded oodle library to decompress oodle paks
+    #[error(
+        "enable the oodle feature or register a loaded oodle library to decompress oodle paks"
+    )]
     Oodle,
+    /// enable the zstd feature to decompress zstd paks
+    #[error("enable the zstd feature to decompress zstd paks")]
+    Zstd,
+    /// enable the lz4 feature to decompress lz4 paks
+    #[error("enable the lz4 feature to decompress lz4 paks")]
+    Lz4,
+    /// compression method has no decompressor - register one on the pak
+    #[error("unsupported compression {0}")]
+    UnsupportedCompression(String),
 
     // internal crate errors
     /// failed to convert to boolean - normally a result of parsing with wrong version
@@ -45,12 +59,30 @@ pub enum Error {
     /// pak is encrypted but no valid key was provided
     #[error("pak is encrypted but no valid key was provided")]
     Encrypted,
-    /// pak could not be parsed with any version - make a github issue
-    #[error("pak could not be parsed with any version")]
+    /// the index decrypted to garbage with the provided key
+    #[error("the provided key doesn't decrypt the pak")]
+    WrongKey,
+    /// no footer was found at the end of the file - normally a result of it not being a pak
+    #[error("no pak footer could be found")]
     Parse,
+    /// the footer has a version number this crate doesn't know about - make a github issue
+    #[error("unsupported pak version {0}")]
+    UnsupportedVersion(u32),
+    /// aes key decoded to the wrong number of bytes
+    #[error("aes key is {0} bytes instead of 32")]
+    KeyLength(usize),
+    /// aes key isn't valid hex or base64
+    #[error("aes key isn't valid hex or base64")]
+    KeyFormat,
     /// no entry found at the specified path
     #[error("no entry could be found at {0}")]
     Missing(String),
+    /// the entry at the specified path is a delete record
+    #[error("{0} is a delete record")]
+    Deleted(String),
+    /// pak was read from a reader so entries must be read with read_from
+    #[error("pak has no path - use read_from with the original reader")]
+    NoPath,
     /// parsing with wrong version - convert error to string to get correct version
     #[error("wrong version - try using v{0}")]
     Version(u32),
@@ -1,4 +1,22 @@
-use byteorder::{ReadBytesExt, LE};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+
+/// turns a path from the index into the name entries are listed by
+#[cfg(feature = "asset-paths")]
+pub fn name(mut path: String) -> String {
+    if let Some(pos) = path.find("Content/") {
+        match path.starts_with("Engine") {
+            true => path.replace_range(pos..pos + 8, ""),
+            false => path.replace_range(0..pos + 8, "Game/"),
+        }
+    }
+    "/".to_string() + &path
+}
+
+/// turns a path from the index into the name entries are listed by
+#[cfg(not(feature = "asset-paths"))]
+pub fn name(path: String) -> String {
+    path
+}
 
 pub trait ReadExt {
     fn read_bool(&mut self) -> Result<bool, super::Error>;
@@ -54,21 +72,8 @@ impl<R: std::io::Read> ReadExt for R {
         Ok(buf)
     }
 
-    #[cfg(feature = "asset-paths")]
-    fn read_name(&mut self) -> Result<String, super::Error> {
-        let mut path = self.read_string()?;
-        if let Some(pos) = path.find("Content/") {
-            match path.starts_with("Engine") {
-                true => path.replace_range(pos..pos + 8, ""),
-                false => path.replace_range(0..pos + 8, "Game/"),
-            }
-        }
-        Ok("/".to_string() + &path)
-    }
-
-    #[cfg(not(feature = "asset-paths"))]
     fn read_name(&mut self) -> Result<String, super::Error> {
-        self.read_string()
+        self.read_string().map(name)
     }
 
     fn read_len(&mut self, len: usize) -> Result<Vec<u8>, super::Error> {
@@ -77,3 +82,37 @@ impl<R: std::io::Read> ReadExt for R {
         Ok(buf)
     }
 }
+
+pub trait WriteExt {
+    fn write_bool(&mut self, value: bool) -> Result<(), super::Error>;
+    fn write_string(&mut self, value: &str) -> Result<(), super::Error>;
+}
+
+impl<W: std::io::Write> WriteExt for W {
+    fn write_bool(&mut self, value: bool) -> Result<(), super::Error> {
+        self.write_u8(value as u8)?;
+        Ok(())
+    }
+
+    fn write_string(&mut self, value: &str) -> Result<(), super::Error> {
+        match value {
+            "" => self.write_i32::<LE>(0)?,
+            value if value.is_ascii() => {
+                // include the null byte
+                self.write_i32::<LE>(value.len() as i32 + 1)?;
+                self.write_all(value.as_bytes())?;
+                self.write_u8(0)?;
+            }
+            value => {
+                let chars: Vec<u16> = value.encode_utf16().collect();
+                // negative length signifies utf16
+                self.write_i32::<LE>(-(chars.len() as i32 + 1))?;
+                for ch in chars {
+                    self.write_u16::<LE>(ch)?;
+                }
+                self.write_u16::<LE>(0)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// how far along an extraction is
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// number of entries finished including those which failed
+    pub entries: usize,
+    /// number of entries being extracted
+    pub total: usize,
+    /// number of bytes written so far
+    pub bytes: u64,
+}
+
+/// options for extracting every entry of a pak
+pub struct ExtractOptions<'a> {
+    threads: usize,
+    progress: Option<Box<dyn Fn(Progress) + Send + Sync + 'a>>,
+    cancel: Option<&'a AtomicBool>,
+}
+
+impl Default for ExtractOptions<'_> {
+    fn default() -> Self {
+        Self {
+            threads: std::thread::available_parallelism().map_or(1, usize::from),
+            progress: None,
+            cancel: None,
+        }
+    }
+}
+
+impl<'a> ExtractOptions<'a> {
+    /// extracts with as many threads as the machine has
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the number of threads extracting entries
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// sets a callback run from the worker threads after each entry
+    pub fn with_progress(mut self, progress: impl Fn(Progress) + Send + Sync + 'a) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// stops extracting once the flag is set - entries being written are still finished
+    pub fn with_cancel(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
+/// the result of extracting a pak
+#[derive(Debug)]
+pub struct Extracted {
+    /// number of entries written
+    pub entries: usize,
+    /// number of bytes written
+    pub bytes: u64,
+    /// entries which failed to extract and why
+    pub errors: Vec<(String, super::Error)>,
+    /// entries skipped because their path would escape the directory
+    pub rejected: Vec<String>,
+    /// whether extraction was cancelled before every entry was done
+    pub cancelled: bool,
+}
+
+impl super::Pak {
+    /// extracts every entry into the directory under its mount point across multiple threads
+    ///
+    /// errors with entries are collected rather than stopping extraction and entries which would escape the directory are skipped
+    pub fn extract_all(
+        &self,
+        dest: impl AsRef<std::path::Path>,
+        options: ExtractOptions,
+    ) -> Result<Extracted, super::Error> {
+        self.extract_with(dest, || self.source(), self.mapped(), options)
+    }
+
+    /// extracts every entry into the directory under its mount point across multiple threads using readers from the function as the pak source
+    ///
+    /// a reader is opened for each thread so it can be shared between all its entries
+    pub fn extract_all_from<R: std::io::Read + std::io::Seek + Send>(
+        &self,
+        dest: impl AsRef<std::path::Path>,
+        open: impl FnMut() -> Result<R, super::Error>,
+        options: ExtractOptions,
+    ) -> Result<Extracted, super::Error> {
+        self.extract_with(dest, open, None, options)
+    }
+
+    /// extracts every entry borrowing the data from the pak if it's in memory
+    fn extract_with<R: std::io::Read + std::io::Seek + Send>(
+        &self,
+        dest: impl AsRef<std::path::Path>,
+        mut open: impl FnMut() -> Result<R, super::Error>,
+        pak: Option<&[u8]>,
+        options: ExtractOptions,
+    ) -> Result<Extracted, super::Error> {
+        let dest = dest.as_ref();
+        let mut entries = Vec::new();
+        let mut rejected = Vec::new();
+        for entry in self.entries() {
+            match self.extract_path(&entry) {
+                Some(path) => entries.push((entry, dest.join(path))),
+                None => rejected.push(entry),
+            }
+        }
+        let threads = options.threads.min(entries.len()).max(1);
+        // each worker keeps the pak open for all its entries
+        let sources = (0..threads)
+            .map(|_| open())
+            .collect::<Result<Vec<_>, _>>()?;
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let written = AtomicUsize::new(0);
+        let bytes = AtomicU64::new(0);
+        let errors = Mutex::new(Vec::new());
+        let work = |mut source: R| loop {
+            if options
+                .cancel
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            {
+                break;
+            }
+            let Some((entry, path)) = entries.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                break;
+            };
+            match self.extract(&mut source, pak, entry, path) {
+                Ok(size) => {
+                    written.fetch_add(1, Ordering::Relaxed);
+                    bytes.fetch_add(size, Ordering::Relaxed);
+                }
+                Err(e) => errors.lock().unwrap().push((entry.clone(), e)),
+            }
+            let finished = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(progress) = &options.progress {
+                progress(Progress {
+                    entries: finished,
+                    total: entries.len(),
+                    bytes: bytes.load(Ordering::Relaxed),
+                })
+            }
+        };
+        std::thread::scope(|scope| {
+            for source in sources {
+                scope.spawn(|| work(source));
+            }
+        });
+        Ok(Extracted {
+            entries: written.into_inner(),
+            bytes: bytes.into_inner(),
+            errors: errors.into_inner().unwrap(),
+            rejected,
+            cancelled: done.into_inner() < entries.len(),
+        })
+    }
+
+    /// the path relative to the output directory the entry extracts to - none if it would escape the directory
+    pub fn extract_path(&self, entry: &str) -> Option<std::path::PathBuf> {
+        // asset paths are given a leading slash which isn't part of the index
+        #[cfg(feature = "asset-paths")]
+        let entry = entry.strip_prefix('/').unwrap_or(entry);
+        // entries are never absolute so this is an attempt to write anywhere
+        if entry.starts_with(['/', '\\']) {
+            return None;
+        }
+        let mut path = std::path::PathBuf::new();
+        for component in (super::set::mount_point(self.mount_point()) + entry).split(['/', '\\']) {
+            match component {
+                "" | "." => continue,
+                ".." => return None,
+                // drive prefixes and alternate data streams on windows
+                _ if component.contains([':', '\0']) => return None,
+                _ => path.push(component),
+            }
+        }
+        path.components().next().is_some().then_some(path)
+    }
+
+    /// writes a single entry to the path creating its directory and gives back the size written
+    ///
+    /// the file is removed if the entry can't be read so no partial files are left
+    fn extract<R: std::io::Read + std::io::Seek>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+        entry: &str,
+        path: &std::path::Path,
+    ) -> Result<u64, super::Error> {
+        use std::io::Write;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = Counter {
+            writer: std::io::BufWriter::new(std::fs::File::create(path)?),
+            written: 0,
+        };
+        match self
+            .read_entry(reader, pak, entry, &mut file)
+            .and_then(|_| Ok(file.flush()?))
+        {
+            Ok(()) => Ok(file.written),
+            Err(e) => {
+                drop(file);
+                let _ = std::fs::remove_file(path);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// counts the bytes written through it
+struct Counter<W: std::io::Write> {
+    writer: W,
+    written: u64,
+}
+
+impl<W: std::io::Write> std::io::Write for Counter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// paths which would write outside the output directory
+    const ESCAPES: [&str; 7] = [
+        "../x",
+        "a/../../x",
+        "a\\..\\..\\z",
+        "C:/x",
+        "c:x",
+        "/x",
+        "\\x",
+    ];
+
+    #[test]
+    fn escaping_paths() -> Result<(), crate::Error> {
+        let dir = std::env::temp_dir().join(format!("unpak-escapes-{}", std::process::id()));
+        let mut writer = crate::PakWriter::new(
+            std::io::Cursor::new(Vec::new()),
+            // the legacy index keeps paths exactly as they're written
+            crate::Version::DeleteRecords,
+            "",
+        );
+        for path in ESCAPES {
+            writer.write_entry(path, b"escaped")?;
+        }
+        writer.write_entry("safe/entry.txt", b"safe")?;
+        let data = writer.finish()?.into_inner();
+        let pak = crate::Pak::from_reader_any(
+            &mut std::io::Cursor::new(&data),
+            #[cfg(feature = "encryption")]
+            None,
+        )?;
+        for path in ESCAPES {
+            // entries are listed with the same renaming as the index
+            assert_eq!(
+                pak.extract_path(&crate::ext::name(path.to_string())),
+                None,
+                "{path}"
+            );
+        }
+        assert_eq!(
+            pak.extract_path(&crate::ext::name("safe/./entry.txt".to_string())),
+            Some(["safe", "entry.txt"].iter().collect())
+        );
+        let out = dir.join("out");
+        let extracted = pak.extract_all_from(
+            &out,
+            || Ok(std::io::Cursor::new(&data)),
+            crate::ExtractOptions::new(),
+        )?;
+        let mut rejected = extracted.rejected;
+        rejected.sort();
+        let mut expected: Vec<_> = ESCAPES
+            .iter()
+            .map(|path| crate::ext::name(path.to_string()))
+            .collect();
+        expected.sort();
+        assert_eq!(rejected, expected);
+        assert_eq!(extracted.entries, 1);
+        // only the safe entry is written and nothing lands next to the output directory
+        let list = |dir: &std::path::Path| -> Result<Vec<_>, crate::Error> {
+            let mut names = Vec::new();
+            for entry in std::fs::read_dir(dir)? {
+                names.push(entry?.file_name());
+            }
+            Ok(names)
+        };
+        assert_eq!(list(&dir)?, ["out"]);
+        assert_eq!(list(&out)?, ["safe"]);
+        assert_eq!(list(&out.join("safe"))?, ["entry.txt"]);
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn extract_from_reader() -> Result<(), crate::Error> {
+        let dir = std::env::temp_dir().join(format!("unpak-extract-{}", std::process::id()));
+        let mut writer = crate::PakWriter::new(
+            std::io::Cursor::new(Vec::new()),
+            crate::Version::Fnv64BugFix,
+            "../../../Project/",
+        );
+        writer.write_entry("dir/entry.txt", b"entry")?;
+        writer.write_entry("root.txt", b"root")?;
+        let data = writer.finish()?.into_inner();
+        let pak = crate::Pak::from_reader_any(
+            &mut std::io::Cursor::new(&data),
+            #[cfg(feature = "encryption")]
+            None,
+        )?;
+        // paks read from readers have no path to reopen
+        assert!(matches!(
+            pak.extract_all(&dir, crate::ExtractOptions::new()),
+            Err(crate::Error::NoPath)
+        ));
+        let extracted = pak.extract_all_from(
+            &dir,
+            || Ok(std::io::Cursor::new(&data)),
+            crate::ExtractOptions::new(),
+        )?;
+        assert!(extracted.errors.is_empty(), "{:?}", extracted.errors);
+        assert_eq!(extracted.entries, 2);
+        assert_eq!(extracted.bytes, 9);
+        assert_eq!(std::fs::read(dir.join("Project/dir/entry.txt"))?, b"entry");
+        assert_eq!(std::fs::read(dir.join("Project/root.txt"))?, b"root");
+        std::fs::remove_dir_all(&dir)?;
+        // entries which fail to read leave no files behind
+        let extracted = pak.extract_all_from(
+            &dir,
+            || Ok(std::io::Cursor::new(&data[..8])),
+            crate::ExtractOptions::new(),
+        )?;
+        assert_eq!(extracted.entries, 0);
+        assert_eq!(extracted.errors.len(), 2);
+        assert!(!dir.join("Project/dir/entry.txt").exists());
+        assert!(!dir.join("Project/root.txt").exists());
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "asset-paths")]
+    #[test]
+    fn extract_asset_paths() -> Result<(), crate::Error> {
+        let dir = std::env::temp_dir().join(format!("unpak-asset-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        for version in [crate::Version::DeleteRecords, crate::Version::Fnv64BugFix] {
+            let mut writer = crate::PakWriter::new(
+                std::io::Cursor::new(Vec::new()),
+                version,
+                "../../../Project/Content/",
+            );
+            writer.write_entry("Maps/map.umap", b"map")?;
+            writer.write_entry("root.txt", b"root")?;
+            let path = dir.join("test.pak");
+            std::fs::write(&path, writer.finish()?.into_inner())?;
+            let pak = crate::Pak::new_any(
+                &path,
+                #[cfg(feature = "encryption")]
+                None,
+            )?;
+            let out = dir.join("out");
+            let extracted = pak.extract_all(&out, crate::ExtractOptions::new())?;
+            assert!(extracted.rejected.is_empty(), "{:?}", extracted.rejected);
+            assert!(extracted.errors.is_empty(), "{:?}", extracted.errors);
+            assert_eq!(extracted.entries, 2);
+            assert_eq!(
+                std::fs::read(out.join("Project/Content/Maps/map.umap"))?,
+                b"map"
+            );
+            assert_eq!(
+                std::fs::read(out.join("Project/Content/root.txt"))?,
+                b"root"
+            );
+            // absolute paths are still rejected once the added slash is gone
+            assert_eq!(pak.extract_path("//etc/passwd"), None);
+        }
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -1,22 +1,55 @@
 use std::str::FromStr;
 
-use super::{ext::ReadExt, Compression, Version};
-use byteorder::{ReadBytesExt, LE};
+use super::{
+    ext::{ReadExt, WriteExt},
+    Compression, Version,
+};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 
-#[derive(Debug)]
-pub struct Footer {
+/// the footer at the end of a pak describing where the index is and how to read it
+#[derive(Clone, Debug)]
+pub struct PakFooter {
+    /// version of the pak
+    pub version: Version,
+    /// guid of the key the pak is encrypted with - zero for the default key
+    pub encryption_guid: u128,
+    /// whether the index is encrypted
     pub encrypted: bool,
+    /// offset of the primary index in the pak
     pub index_offset: u64,
+    /// size of the primary index
     pub index_size: u64,
+    /// sha1 hash of the primary index
+    pub index_hash: [u8; 20],
+    /// whether the index is frozen
+    pub frozen: bool,
+    /// compression methods the entries can use
     pub compression: Vec<Compression>,
 }
 
-impl Footer {
-    pub fn new(reader: &mut std::fs::File, version: Version) -> Result<Self, super::Error> {
-        // encryption key uuid
-        if version >= Version::EncryptionKeyUuid {
-            reader.read_u128::<LE>()?;
-        }
+impl PakFooter {
+    /// reads only the footer of a pak file with the version detected
+    pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self, super::Error> {
+        Self::from_reader(&mut std::fs::File::open(path)?)
+    }
+
+    /// reads only the footer of a pak from any reader with the version detected
+    pub fn from_reader<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+    ) -> Result<Self, super::Error> {
+        let version = Version::detect(reader)?;
+        Self::read(reader, version)
+    }
+
+    pub(crate) fn read<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        version: Version,
+    ) -> Result<Self, super::Error> {
+        reader.seek(std::io::SeekFrom::End(-version.footer_size()))?;
+        let encryption_guid = match version >= Version::EncryptionKeyUuid {
+            true => reader.read_u128::<LE>()?,
+            false => 0,
+        };
         let encrypted = version >= Version::IndexEncryption && reader.read_bool()?;
         let magic = reader.read_u32::<LE>()?;
         if magic != super::MAGIC {
@@ -29,29 +62,21 @@ impl Footer {
         }
         let index_offset = reader.read_u64::<LE>()?;
         let index_size = reader.read_u64::<LE>()?;
-        // hash
-        reader.read_guid()?;
-        // frozen index
-        if version == Version::FrozenIndex {
-            reader.read_bool()?;
-        }
-        let mut compression = Vec::with_capacity(match version {
-            ver if ver < Version::FNameBasedCompression => 0,
-            ver if ver == Version::FNameBasedCompression => 4,
-            _ => 5,
-        });
-        for _ in 0..compression.capacity() {
-            compression.push(
-                Compression::from_str(
-                    &reader
-                        .read_len(32)?
-                        .iter()
-                        // filter out whitespace and convert to char
-                        .filter_map(|&ch| (ch != 0).then_some(ch as char))
-                        .collect::<String>(),
-                )
-                .unwrap_or_default(),
-            )
+        let index_hash = reader.read_guid()?;
+        let frozen = version == Version::FrozenIndex && reader.read_bool()?;
+        let mut compression = Vec::with_capacity(version.compression_slots());
+        for _ in 0..version.compression_slots() {
+            let name = reader
+                .read_len(32)?
+                .iter()
+                // filter out whitespace and convert to char
+                .filter_map(|&ch| (ch != 0).then_some(ch as char))
+                .collect::<String>();
+            compression.push(match name.is_empty() {
+                true => Compression::None,
+                // unknown methods are kept so a decompressor can be registered for them
+                false => Compression::from_str(&name).unwrap_or_default(),
+            })
         }
         if version < Version::FNameBasedCompression {
             compression.push(Compression::Zlib);
@@ -60,10 +85,75 @@ impl Footer {
         }
         compression.dedup();
         Ok(Self {
+            version,
+            encryption_guid,
             encrypted,
             index_offset,
             index_size,
+            index_hash,
+            frozen,
             compression,
         })
     }
+
+    pub(crate) fn write<W: std::io::Write>(&self, writer: &mut W) -> Result<(), super::Error> {
+        let version = self.version;
+        if version >= Version::EncryptionKeyUuid {
+            writer.write_u128::<LE>(self.encryption_guid)?;
+        }
+        if version >= Version::IndexEncryption {
+            writer.write_bool(self.encrypted)?;
+        }
+        writer.write_u32::<LE>(super::MAGIC)?;
+        writer.write_u32::<LE>(version.as_u32())?;
+        writer.write_u64::<LE>(self.index_offset)?;
+        writer.write_u64::<LE>(self.index_size)?;
+        writer.write_all(&self.index_hash)?;
+        if version == Version::FrozenIndex {
+            writer.write_bool(self.frozen)?;
+        }
+        let slots = version.compression_slots();
+        if version >= Version::FNameBasedCompression && self.compression.len() > slots {
+            return Err(super::Error::Other(format!(
+                "v{} paks only have {slots} compression slots",
+                version.as_u32()
+            )));
+        }
+        if let Some(compression) = self
+            .compression
+            .iter()
+            .find(|compression| compression.to_string().len() > 32)
+        {
+            return Err(super::Error::Other(format!(
+                "{compression} is longer than the 32 bytes of a compression slot"
+            )));
+        }
+        for i in 0..slots {
+            let mut name = [0; 32];
+            if let Some(compression) = self.compression.get(i) {
+                let compression = compression.to_string();
+                name[..compression.len()].copy_from_slice(compression.as_bytes());
+            }
+            writer.write_all(&name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn long_compression_name() {
+        let footer = super::PakFooter {
+            version: super::Version::Fnv64BugFix,
+            encryption_guid: 0,
+            encrypted: false,
+            index_offset: 0,
+            index_size: 0,
+            index_hash: [0; 20],
+            frozen: false,
+            compression: vec![super::Compression::Other("a".repeat(33))],
+        };
+        assert!(footer.write(&mut Vec::new()).is_err());
+    }
 }
@@ -0,0 +1,96 @@
+use super::Version;
+
+/// fnv64 as implemented by unreal which adds the seed to the offset basis
+pub fn fnv64(data: impl IntoIterator<Item = u8>, seed: u64) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x00000100000001b3;
+    data.into_iter()
+        .fold(OFFSET.wrapping_add(seed), |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(PRIME)
+        })
+}
+
+/// hashes a path relative to the mount point as stored in the path hash index
+pub fn path_hash(path: &str, seed: u64, version: Version) -> u64 {
+    let lower = path.to_lowercase();
+    let bytes = lower.encode_utf16().flat_map(u16::to_le_bytes);
+    match version >= Version::Fnv64BugFix {
+        true => fnv64(bytes, seed),
+        // before the fix the length in characters was used as the length in bytes
+        false => fnv64(bytes.take(lower.encode_utf16().count()), seed),
+    }
+}
+
+/// the sha1 hash used to verify entries and indices
+pub fn sha1(bytes: &[u8]) -> [u8; 20] {
+    use sha1::Digest;
+    sha1::Sha1::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fnv64, path_hash, Version};
+
+    #[test]
+    fn fnv() {
+        // fnv-1a reference values
+        assert_eq!(fnv64(*b"a", 0), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv64(*b"foobar", 0), 0x85944171f73967e8);
+        // the seed is added to the offset basis
+        assert_eq!(fnv64(*b"a", 1), 0xaf63df4c8601f1a5);
+        assert_eq!(fnv64(*b"foobar", 0x1234), 0x94e745346512b944);
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            path_hash("Content/A.txt", 0x1234, Version::Fnv64BugFix),
+            0xbbd7cc1264f964f8
+        );
+        // paths are lowercased first
+        assert_eq!(
+            path_hash("content/a.txt", 0x1234, Version::Fnv64BugFix),
+            0xbbd7cc1264f964f8
+        );
+        // only the first half of the utf-16 bytes were hashed before the fix
+        assert_eq!(
+            path_hash("Content/A.txt", 0x1234, Version::PathHashIndex),
+            0x3090974c6ca2d4b8
+        );
+    }
+
+    #[test]
+    fn read_hashed() -> Result<(), crate::Error> {
+        for version in [Version::PathHashIndex, Version::Fnv64BugFix] {
+            let mut writer = crate::PakWriter::new(std::io::Cursor::new(Vec::new()), version, "")
+                .with_path_hash_seed(0x1234)
+                .with_full_directory_index(false);
+            writer.write_entry("dir/entry.txt", b"entry")?;
+            let data = writer.finish()?.into_inner();
+            let pak = crate::Pak::from_reader(
+                &mut std::io::Cursor::new(&data),
+                version,
+                #[cfg(feature = "encryption")]
+                None,
+            )?;
+            let hash = path_hash("dir/entry.txt", 0x1234, version);
+            assert_eq!(pak.path_hash("Dir/Entry.txt"), hash);
+            let mut entry = Vec::new();
+            pak.read_hash_from(&mut std::io::Cursor::new(&data), hash, &mut entry)?;
+            assert_eq!(entry, b"entry");
+            // paths fall back to their hash when the full directory index is missing
+            let mut entry = Vec::new();
+            pak.read_from(
+                &mut std::io::Cursor::new(&data),
+                "DIR/entry.txt",
+                &mut entry,
+            )?;
+            assert_eq!(entry, b"entry");
+            assert!(matches!(
+                pak.read_hash_from(&mut std::io::Cursor::new(&data), hash ^ 1, &mut Vec::new()),
+                Err(crate::Error::Missing(_))
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+/// a 256-bit aes key parsed from hex or base64
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AesKey([u8; 32]);
+
+impl AesKey {
+    /// creates a key from its bytes
+    pub fn new(key: &[u8]) -> Result<Self, super::Error> {
+        match key.try_into() {
+            Ok(key) => Ok(Self(key)),
+            Err(_) => Err(super::Error::KeyLength(key.len())),
+        }
+    }
+
+    /// gets the bytes of the key
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl FromStr for AesKey {
+    type Err = super::Error;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        use base64::Engine;
+        let key = key.trim();
+        let bytes = match key.strip_prefix("0x").or_else(|| key.strip_prefix("0X")) {
+            Some(hex) => hex::decode(hex).ok(),
+            // base64 keys are always padded so can't be mistaken for hex
+            None if key.chars().all(|ch| ch.is_ascii_hexdigit()) => hex::decode(key).ok(),
+            None => base64::engine::general_purpose::STANDARD.decode(key).ok(),
+        };
+        match bytes {
+            Some(bytes) => Self::new(&bytes),
+            None => Err(super::Error::KeyFormat),
+        }
+    }
+}
+
+impl AsRef<[u8]> for AesKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<AesKey> for Vec<u8> {
+    fn from(key: AesKey) -> Self {
+        key.0.to_vec()
+    }
+}
+
+/// keys used to decrypt paks identified by the encryption guid in their footer
+#[derive(Clone, Default, Debug)]
+pub struct KeyChain {
+    keys: hashbrown::HashMap<u128, Vec<u8>>,
+}
+
+impl KeyChain {
+    /// creates an empty keychain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds a key for paks with the guid - the zero guid is the default key
+    pub fn with_key(mut self, guid: u128, key: impl Into<Vec<u8>>) -> Self {
+        self.insert(guid, key);
+        self
+    }
+
+    /// adds a key for paks with the guid - the zero guid is the default key
+    pub fn insert(&mut self, guid: u128, key: impl Into<Vec<u8>>) {
+        self.keys.insert(guid, key.into());
+    }
+
+    /// gets the key for paks with the guid falling back to the default key
+    pub fn get(&self, guid: u128) -> Option<&[u8]> {
+        self.keys
+            .get(&guid)
+            .or_else(|| self.keys.get(&0))
+            .map(Vec::as_slice)
+    }
+
+    /// whether there are no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl From<&[u8]> for KeyChain {
+    fn from(key: &[u8]) -> Self {
+        Self::new().with_key(0, key)
+    }
+}
+
+impl From<Option<&[u8]>> for KeyChain {
+    fn from(key: Option<&[u8]>) -> Self {
+        key.map(Self::from).unwrap_or_default()
+    }
+}
+
+impl From<&KeyChain> for KeyChain {
+    fn from(keys: &KeyChain) -> Self {
+        keys.clone()
+    }
+}
+
+impl From<&AesKey> for KeyChain {
+    fn from(key: &AesKey) -> Self {
+        Self::from(key.as_ref())
+    }
+}
+
+impl From<AesKey> for KeyChain {
+    fn from(key: AesKey) -> Self {
+        Self::from(&key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AesKey;
+
+    const HEX: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn parse() -> Result<(), crate::Error> {
+        let bytes: Vec<u8> = (0..32).collect();
+        let key = AesKey::new(&bytes)?;
+        assert_eq!(HEX.parse::<AesKey>()?, key);
+        assert_eq!(HEX.to_uppercase().parse::<AesKey>()?, key);
+        assert_eq!(format!("0x{HEX}").parse::<AesKey>()?, key);
+        assert_eq!(format!("0X{}", HEX.to_uppercase()).parse::<AesKey>()?, key);
+        // surrounding whitespace from files or the command line is ignored
+        assert_eq!(format!(" 0x{HEX}\n").parse::<AesKey>()?, key);
+        assert_eq!(
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=".parse::<AesKey>()?,
+            key
+        );
+        assert_eq!(key.as_bytes().as_slice(), bytes.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        // valid encodings of the wrong number of bytes
+        assert!(matches!(
+            HEX[..62].parse::<AesKey>(),
+            Err(crate::Error::KeyLength(31))
+        ));
+        assert!(matches!(
+            format!("0x{HEX}00").parse::<AesKey>(),
+            Err(crate::Error::KeyLength(33))
+        ));
+        assert!(matches!(
+            "AAAAAAAAAAAAAAAAAAAAAA==".parse::<AesKey>(),
+            Err(crate::Error::KeyLength(16))
+        ));
+        assert!(matches!(
+            AesKey::new(&[0; 8]),
+            Err(crate::Error::KeyLength(8))
+        ));
+        // neither hex nor base64
+        for key in ["0xnothex", "0x123", "not base64!", "abc"] {
+            assert!(
+                matches!(key.parse::<AesKey>(), Err(crate::Error::KeyFormat)),
+                "{key}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_key() {
+        // a bare none has to infer as there's only one optional key
+        assert!(crate::Pak::new_any("missing.pak", None).is_err());
+        assert!(crate::Pak::new("missing.pak", crate::Version::Fnv64BugFix, None).is_err());
+    }
+}
@@ -1,23 +1,56 @@
 #![allow(dead_code)]
+mod decompressor;
 mod entry;
 mod error;
 mod ext;
+mod extract;
 mod footer;
+mod hash;
+#[cfg(feature = "encryption")]
+mod key;
+#[cfg(any(feature = "oodle", feature = "oodle-dynamic"))]
+mod oodle;
 mod pak;
+mod set;
+mod stream;
+mod writer;
 
-pub use {error::*, pak::*};
+#[cfg(feature = "encryption")]
+pub use key::{AesKey, KeyChain};
+#[cfg(feature = "oodle-dynamic")]
+pub use oodle::OodleLibrary;
+pub use {
+    decompressor::Decompressor, entry::EntryInfo, error::*, extract::*, footer::PakFooter,
+    hash::path_hash, pak::*, set::*, stream::*, writer::*,
+};
 
 /// the magic used to identify a pak
 pub const MAGIC: u32 = 0x5A6F12E1;
 
 /// different compressions that a pak can use
-#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, strum::EnumString)]
+#[derive(Default, Clone, PartialEq, Eq, Debug, strum::AsRefStr, strum::EnumString)]
+#[strum(ascii_case_insensitive)]
 pub enum Compression {
     #[default]
     None,
     Zlib,
     Gzip,
     Oodle,
+    Zstd,
+    #[strum(serialize = "LZ4")]
+    Lz4,
+    /// a method the crate doesn't know about which can be read with a registered decompressor
+    #[strum(default)]
+    Other(String),
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compression::Other(name) => f.write_str(name),
+            compression => f.write_str(compression.as_ref()),
+        }
+    }
 }
 
 /// the possible versions that a pak file can be
@@ -56,6 +89,48 @@ impl Version {
         <Version as strum::IntoEnumIterator>::iter()
     }
 
+    /// detects the version from the magic and version number in the footer
+    pub fn detect<R: std::io::Read + std::io::Seek>(reader: &mut R) -> Result<Self, Error> {
+        use byteorder::{ReadBytesExt, LE};
+        use std::io::SeekFrom;
+        let len = reader.seek(SeekFrom::End(0))?;
+        let mut found = None;
+        // newest first so later versions win if footers somehow overlap
+        for version in Version::iter().rev() {
+            let footer = version.footer_size() as u64;
+            if footer > len {
+                continue;
+            }
+            reader.seek(SeekFrom::Start(len - footer + version.magic_offset()))?;
+            if reader.read_u32::<LE>()? != MAGIC {
+                continue;
+            }
+            let number = reader.read_u32::<LE>()?;
+            if number != version.as_u32() {
+                found = Some(number);
+                continue;
+            }
+            // both v8 layouts share a version number so check the compression names make sense
+            if version >= Version::FNameBasedCompression {
+                reader.seek(SeekFrom::End(-32 * version.compression_slots() as i64))?;
+                let mut names = vec![0; 32 * version.compression_slots()];
+                reader.read_exact(&mut names)?;
+                if !names.chunks(32).all(|name| {
+                    let end = name.iter().position(|&ch| ch == 0).unwrap_or(32);
+                    name[..end].iter().all(u8::is_ascii_graphic)
+                        && name[end..].iter().all(|&ch| ch == 0)
+                }) {
+                    continue;
+                }
+            }
+            return Ok(version);
+        }
+        match found {
+            Some(number) => Err(Error::UnsupportedVersion(number)),
+            None => Err(Error::Parse),
+        }
+    }
+
     fn as_u32(self) -> u32 {
         match self {
             Version::Initial => 1,
@@ -73,6 +148,29 @@ impl Version {
         }
     }
 
+    /// where the magic is relative to the start of the footer
+    fn magic_offset(self) -> u64 {
+        let mut offset = 0;
+        if self >= Version::EncryptionKeyUuid {
+            // encryption uuid: u128
+            offset += 16;
+        }
+        if self >= Version::IndexEncryption {
+            // encrypted: bool
+            offset += 1;
+        }
+        offset
+    }
+
+    /// the number of compression names at the end of the footer
+    fn compression_slots(self) -> usize {
+        match self {
+            ver if ver < Version::FNameBasedCompression => 0,
+            Version::FNameBasedCompression => 4,
+            _ => 5,
+        }
+    }
+
     fn footer_size(self) -> i64 {
         // (magic + version): u32 + (offset + size): u64 + hash: [u8; 20]
         let mut size = 4 + 4 + 8 + 8 + 20;
@@ -113,3 +211,29 @@ fn decrypt(key: Option<&aes::Aes256Dec>, bytes: &mut [u8]) -> Result<(), Error>
         None => Err(Error::Encrypted),
     }
 }
+
+#[cfg(feature = "encryption")]
+fn encrypt(key: &aes::Aes256Enc, bytes: &mut Vec<u8>) {
+    use aes::cipher::BlockEncrypt;
+    // pad to the aes block size
+    bytes.resize((bytes.len() + 15) & !15, 0);
+    for chunk in bytes.chunks_mut(16) {
+        key.encrypt_block(aes::Block::from_mut_slice(chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn compression_names() {
+        use super::Compression;
+        use std::str::FromStr;
+        assert_eq!(Compression::from_str("zlib"), Ok(Compression::Zlib));
+        assert_eq!(Compression::from_str("OODLE"), Ok(Compression::Oodle));
+        assert_eq!(Compression::from_str("lz4"), Ok(Compression::Lz4));
+        assert_eq!(
+            Compression::from_str("Custom"),
+            Ok(Compression::Other("Custom".to_string()))
+        );
+    }
+}
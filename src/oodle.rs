@@ -0,0 +1,122 @@
+use std::io;
+
+/// the signature of OodleLZ_Decompress
+type Decompress = unsafe extern "C" fn(
+    *const u8,
+    usize,
+    *mut u8,
+    usize,
+    u32,
+    u32,
+    u32,
+    u64,
+    usize,
+    u64,
+    u64,
+    *mut u8,
+    usize,
+    u32,
+) -> i32;
+
+/// decompresses a block with whichever OodleLZ_Decompress is available
+pub(crate) fn decompress<W: io::Write + ?Sized>(
+    function: Decompress,
+    data: &[u8],
+    uncompressed: usize,
+    buf: &mut W,
+) -> Result<(), super::Error> {
+    let mut scratch = vec![0; uncompressed];
+    if unsafe {
+        function(
+            data.as_ptr(),
+            data.len(),
+            scratch.as_mut_ptr(),
+            scratch.len(),
+            1,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            std::ptr::null_mut(),
+            0,
+            3,
+        ) == 0
+    } {
+        return Err(super::Error::OodleLZ_Decompress);
+    }
+    buf.write_all(scratch.as_slice())?;
+    Ok(())
+}
+
+/// oodle loaded from a shared library at runtime - register it as the decompressor for oodle
+#[cfg(feature = "oodle-dynamic")]
+pub struct OodleLibrary {
+    decompress: Decompress,
+    // the function pointer is only valid while the library is loaded
+    _library: libloading::Library,
+}
+
+#[cfg(feature = "oodle-dynamic")]
+impl OodleLibrary {
+    /// loads oodle from a shared library like oo2core_9_win64.dll or liboo2corelinux64.so.9
+    pub fn load(path: impl AsRef<std::ffi::OsStr>) -> Result<Self, super::Error> {
+        let path = path.as_ref();
+        let error = |e: libloading::Error| {
+            super::Error::OodleLibrary(format!("{}: {e}", path.to_string_lossy()))
+        };
+        unsafe {
+            let library = libloading::Library::new(path).map_err(error)?;
+            let decompress = *library
+                .get::<Decompress>(b"OodleLZ_Decompress\0")
+                .map_err(error)?;
+            Ok(Self {
+                decompress,
+                _library: library,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "oodle-dynamic")]
+impl super::Decompressor for OodleLibrary {
+    fn decompress(
+        &self,
+        data: &[u8],
+        uncompressed: usize,
+        buf: &mut dyn io::Write,
+    ) -> Result<(), super::Error> {
+        decompress(self.decompress, data, uncompressed, buf)
+    }
+}
+
+#[cfg(feature = "oodle")]
+#[cfg_attr(target_os = "windows", link(name = "oo2core_win64", kind = "static"))]
+#[cfg_attr(target_os = "macos", link(name = "liboo2coremac64", kind = "static"))]
+#[cfg_attr(
+    all(target_os = "linux", target_arch = "x86_64"),
+    link(name = "liboo2corelinux64", kind = "static")
+)]
+#[cfg_attr(
+    all(target_os = "linux", target_arch = "arm"),
+    link(name = "liboo2corelinuxarm64", kind = "static")
+)]
+extern "C" {
+    pub(crate) fn OodleLZ_Decompress(
+        compBuf: *const u8,
+        compBufSize: usize,
+        rawBuf: *mut u8,
+        rawLen: usize,
+        fuzzSafe: u32,
+        checkCRC: u32,
+        verbosity: u32,
+        decBufBase: u64,
+        decBufSize: usize,
+        fpCallback: u64,
+        callbackUserData: u64,
+        decoderMemory: *mut u8,
+        decoderMemorySize: usize,
+        threadPhase: u32,
+    ) -> i32;
+}
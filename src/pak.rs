@@ -1,164 +1,335 @@
 use super::{Compression, Version};
 use std::io;
 
+/// a part of the pak whose hash didn't match the recorded hash
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum HashMismatch {
+    /// the primary index
+    Index,
+    /// the path hash index
+    PathHashIndex,
+    /// the full directory index
+    FullDirectoryIndex,
+    /// the data of the entry at this path
+    Entry(String),
+}
+
+/// where an index is stored and its recorded hash
+#[derive(Debug)]
+struct IndexBlock {
+    offset: u64,
+    size: u64,
+    hash: [u8; 20],
+}
+
+impl IndexBlock {
+    fn new<R: io::Read>(reader: &mut R) -> Result<Self, super::Error> {
+        use super::ext::ReadExt;
+        use byteorder::{ReadBytesExt, LE};
+        Ok(Self {
+            offset: reader.read_u64::<LE>()?,
+            size: reader.read_u64::<LE>()?,
+            hash: reader.read_guid()?,
+        })
+    }
+
+    /// reads and decrypts the block
+    fn read<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        encrypted: bool,
+        #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
+    ) -> Result<Vec<u8>, super::Error> {
+        use super::ext::ReadExt;
+        reader.seek(io::SeekFrom::Start(self.offset))?;
+        #[allow(unused_mut)]
+        let mut data = reader.read_len(self.size as usize)?;
+        if encrypted {
+            #[cfg(feature = "encryption")]
+            super::decrypt(key, &mut data)?;
+            #[cfg(not(feature = "encryption"))]
+            return Err(super::Error::Encryption);
+        }
+        Ok(data)
+    }
+}
+
 /// the pak file with all the goodies
 #[derive(Debug)]
 pub struct Pak {
     version: Version,
-    path: std::path::PathBuf,
+    path: Option<std::path::PathBuf>,
     mount_point: String,
     compression: Vec<Compression>,
+    decompressors: super::decompressor::Decompressors,
     #[cfg(feature = "encryption")]
     key: Option<aes::Aes256Dec>,
+    encrypted: bool,
+    index: IndexBlock,
+    path_hash_index: Option<IndexBlock>,
+    full_dir_index: Option<IndexBlock>,
     entries: hashbrown::HashMap<String, super::entry::Entry>,
+    path_hash_seed: u64,
+    // entries keyed by the hash of their path
+    path_hashes: hashbrown::HashMap<u64, super::entry::Entry>,
+    // path hashes of entries whose paths are known
+    named: hashbrown::HashSet<u64>,
+    #[cfg(feature = "mmap")]
+    map: Option<memmap2::Mmap>,
 }
 
 impl Pak {
     /// reads a pak file with a known version
+    ///
+    /// with the mmap feature the file is mapped and mustn't be modified while the pak is alive
     pub fn new(
         path: impl AsRef<std::path::Path>,
         version: super::Version,
-        #[cfg(feature = "encryption")] key_hash: Option<&[u8]>,
+        #[cfg(feature = "encryption")] keys: impl Into<super::KeyChain>,
+    ) -> Result<Self, super::Error> {
+        Pak::open(path, |reader| {
+            Pak::from_reader(
+                reader,
+                version,
+                #[cfg(feature = "encryption")]
+                keys,
+            )
+        })
+    }
+
+    /// reads a pak from any reader with a known version
+    pub fn from_reader<R: io::Read + io::Seek>(
+        reader: &mut R,
+        version: super::Version,
+        #[cfg(feature = "encryption")] keys: impl Into<super::KeyChain>,
     ) -> Result<Self, super::Error> {
         use super::ext::ReadExt;
         use byteorder::{ReadBytesExt, LE};
         use io::Seek;
-        let mut reader = std::fs::File::open(&path)?;
         // read footer to get index, encryption & compression info
-        reader.seek(io::SeekFrom::End(-version.footer_size()))?;
-        let footer = super::footer::Footer::new(&mut reader, version)?;
-        // read index to get all the entry info
-        reader.seek(io::SeekFrom::Start(footer.index_offset))?;
-        #[allow(unused_mut)]
-        let mut index = reader.read_len(footer.index_size as usize)?;
+        let footer = super::footer::PakFooter::read(reader, version)?;
+        // entries can be encrypted even if the index isn't
         #[cfg(feature = "encryption")]
-        let mut key = None;
-        // decrypt index if needed
-        if footer.encrypted {
+        let key = decryptor(&keys.into(), footer.encryption_guid)?;
+        // read index to get all the entry info
+        let index = read_index(
+            reader,
+            &footer,
             #[cfg(feature = "encryption")]
-            {
-                let Some(hash) = key_hash else {
-                    return Err(super::Error::Encrypted);
-                };
-                use aes::cipher::KeyInit;
-                let Ok(dec) = aes::Aes256Dec::new_from_slice(hash) else {
-                    return Err(super::Error::Aes)
-                };
-                key = Some(dec);
-                super::decrypt(key.as_ref(), &mut index)?;
-            }
-            #[cfg(not(feature = "encryption"))]
-            return Err(super::Error::Encryption);
-        }
+            key.as_ref(),
+        )?;
         let mut index = io::Cursor::new(index);
         let mount_point = index.read_string()?;
         // with_capacity doesn't set capacity exactly
         let mut entries = hashbrown::HashMap::new();
+        let mut path_hashes = hashbrown::HashMap::new();
+        let mut named = hashbrown::HashSet::new();
+        let mut path_hash_seed = 0;
+        let mut path_hash_index = None;
+        let mut full_dir_index = None;
         if version >= Version::PathHashIndex {
             // entry count
             index.read_u32::<LE>()?;
-            // path hash seed
-            index.read_u64::<LE>()?;
-            // path hash
+            path_hash_seed = index.read_u64::<LE>()?;
             if index.read_u32::<LE>()? != 0 {
-                // offset
-                index.read_u64::<LE>()?;
-                // size
-                index.read_u64::<LE>()?;
-                // hash
-                index.read_guid()?;
-                // no need to look at the path hash information
-            }
-            let mut files = Vec::new();
-            // full directory index
+                path_hash_index = Some(IndexBlock::new(&mut index)?);
+            }
             if index.read_u32::<LE>()? != 0 {
-                let offset = index.read_u64::<LE>()?;
-                let size = index.read_u64::<LE>()?;
-                // hash
-                index.read_guid()?;
-                reader.seek(io::SeekFrom::Start(offset))?;
-                #[allow(unused_mut)]
-                let mut full_dir = reader.read_len(size as usize)?;
-                if footer.encrypted {
+                full_dir_index = Some(IndexBlock::new(&mut index)?);
+            }
+            let size = index.read_u32::<LE>()? as usize;
+            let mut encoded = io::Cursor::new(index.read_len(size)?);
+            // records which couldn't be encoded such as delete records
+            let unencoded =
+                ReadExt::read_array(&mut index, |index| super::entry::Entry::new(index, version))?;
+            let mut locate = |location: i32| -> Result<_, super::Error> {
+                Ok(match location {
+                    // invalid location
+                    i32::MIN => None,
+                    offset if offset >= 0 => {
+                        encoded.seek(io::SeekFrom::Start(offset as u64))?;
+                        Some(super::entry::Entry::from_encoded(&mut encoded)?)
+                    }
+                    // negative locations are indices into the unencoded records
+                    i => unencoded.get((-(i + 1)) as usize).cloned(),
+                })
+            };
+            if let Some(block) = &path_hash_index {
+                let mut path_hash = io::Cursor::new(block.read(
+                    reader,
+                    footer.encrypted,
                     #[cfg(feature = "encryption")]
-                    super::decrypt(key.as_ref(), &mut full_dir)?;
-                    #[cfg(not(feature = "encryption"))]
-                    return Err(super::Error::Encryption);
+                    key.as_ref(),
+                )?);
+                // the pruned directory index after isn't needed
+                for _ in 0..path_hash.read_u32::<LE>()? {
+                    let hash = path_hash.read_u64::<LE>()?;
+                    if let Some(entry) = locate(path_hash.read_i32::<LE>()?)? {
+                        path_hashes.insert(hash, entry);
+                    }
                 }
-                let mut full_dir = io::Cursor::new(full_dir);
+            }
+            if let Some(block) = &full_dir_index {
+                let mut full_dir = io::Cursor::new(block.read(
+                    reader,
+                    footer.encrypted,
+                    #[cfg(feature = "encryption")]
+                    key.as_ref(),
+                )?);
                 for _ in 0..full_dir.read_u32::<LE>()? {
-                    let dir = full_dir.read_name()?;
+                    let dir = match full_dir.read_string()? {
+                        // files in the root directory aren't prefixed
+                        dir if dir == "/" => String::new(),
+                        dir => dir,
+                    };
                     for _ in 0..full_dir.read_u32::<LE>()? {
-                        files.push((
-                            dir.clone() + &full_dir.read_string()?,
-                            full_dir.read_u32::<LE>()?,
-                        ));
+                        let file = dir.clone() + &full_dir.read_string()?;
+                        if let Some(entry) = locate(full_dir.read_i32::<LE>()?)? {
+                            // the hash is of the path before it's renamed
+                            named.insert(super::path_hash(&file, path_hash_seed, version));
+                            entries.insert(super::ext::name(file), entry);
+                        }
                     }
                 }
             }
-            let size = index.read_u32::<LE>()? as usize;
-            let mut encoded = io::Cursor::new(index.read_len(size)?);
-            for (file, offset) in files {
-                encoded.seek(io::SeekFrom::Start(offset as u64))?;
-                entries.insert(file, super::entry::Entry::from_encoded(&mut encoded)?);
+        } else {
+            for _ in 0..index.read_u32::<LE>()? as usize {
+                entries.insert(
+                    index.read_name()?,
+                    super::entry::Entry::new(&mut index, version)?,
+                );
             }
         }
-        for _ in 0..index.read_u32::<LE>()? as usize {
-            entries.insert(
-                index.read_name()?,
-                super::entry::Entry::new(&mut index, version)?,
-            );
-        }
 
         Ok(Self {
             version,
-            path: path.as_ref().to_path_buf(),
+            path: None,
             mount_point,
             compression: footer.compression,
+            decompressors: Default::default(),
             #[cfg(feature = "encryption")]
             key,
+            encrypted: footer.encrypted,
+            index: IndexBlock {
+                offset: footer.index_offset,
+                size: footer.index_size,
+                hash: footer.index_hash,
+            },
+            path_hash_index,
+            full_dir_index,
             entries,
+            path_hash_seed,
+            path_hashes,
+            named,
+            #[cfg(feature = "mmap")]
+            map: None,
         })
     }
 
     /// reads a pak file with a guessed version
+    ///
+    /// with the mmap feature the file is mapped and mustn't be modified while the pak is alive
     pub fn new_any(
         path: impl AsRef<std::path::Path>,
-        #[cfg(feature = "encryption")] key: Option<&[u8]>,
+        #[cfg(feature = "encryption")] keys: impl Into<super::KeyChain>,
     ) -> Result<Pak, super::Error> {
-        for ver in Version::iter().rev() {
-            match Pak::new(
-                &path,
-                ver,
+        Pak::open(path, |reader| {
+            Pak::from_reader_any(
+                reader,
                 #[cfg(feature = "encryption")]
-                key,
-            ) {
-                Ok(pak) => return Ok(pak),
-                Err(e) => match e {
-                    crate::Error::Io(io) => match io.kind() {
-                        io::ErrorKind::NotFound
-                        | io::ErrorKind::PermissionDenied
-                        | io::ErrorKind::AlreadyExists
-                        | io::ErrorKind::WouldBlock
-                        | io::ErrorKind::InvalidInput
-                        | io::ErrorKind::InvalidData
-                        | io::ErrorKind::TimedOut
-                        | io::ErrorKind::WriteZero
-                        | io::ErrorKind::Interrupted
-                        | io::ErrorKind::Unsupported => return Err(io.into()),
-                        // eof or out of memory would indicate a wrong version
-                        _ => continue,
-                    },
-                    crate::Error::Aes
-                    | crate::Error::IntoInner(_)
-                    | crate::Error::Encryption
-                    | crate::Error::Compression
-                    | crate::Error::Encrypted => return Err(e),
-                    _ => continue,
-                },
-            }
-        }
-        Err(super::Error::Parse)
+                keys,
+            )
+        })
+    }
+
+    /// reads the pak at the path from the mapping if there is one and remembers the path
+    fn open(
+        path: impl AsRef<std::path::Path>,
+        read: impl FnOnce(&mut Source) -> Result<Pak, super::Error>,
+    ) -> Result<Pak, super::Error> {
+        let file = std::fs::File::open(&path)?;
+        #[cfg(feature = "mmap")]
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        #[cfg(feature = "mmap")]
+        let mut pak = read(&mut Source::Map(io::Cursor::new(&map)))?;
+        #[cfg(not(feature = "mmap"))]
+        let mut pak = read(&mut Source::File(file))?;
+        #[cfg(feature = "mmap")]
+        {
+            pak.map = Some(map);
+        }
+        pak.path = Some(path.as_ref().to_path_buf());
+        Ok(pak)
+    }
+
+    /// reads a pak from any reader with the version detected from its footer
+    pub fn from_reader_any<R: io::Read + io::Seek>(
+        reader: &mut R,
+        #[cfg(feature = "encryption")] keys: impl Into<super::KeyChain>,
+    ) -> Result<Pak, super::Error> {
+        let version = Version::detect(reader)?;
+        Pak::from_reader(
+            reader,
+            version,
+            #[cfg(feature = "encryption")]
+            keys,
+        )
+    }
+
+    /// checks whether the keys decrypt the pak file without reading the entries
+    ///
+    /// any key passes if the index isn't encrypted
+    #[cfg(feature = "encryption")]
+    pub fn test_key(
+        path: impl AsRef<std::path::Path>,
+        keys: impl Into<super::KeyChain>,
+    ) -> Result<bool, super::Error> {
+        Pak::test_key_from(&mut std::fs::File::open(path)?, keys)
+    }
+
+    /// checks whether the keys decrypt the pak from any reader without reading the entries
+    ///
+    /// any key passes if the index isn't encrypted
+    #[cfg(feature = "encryption")]
+    pub fn test_key_from<R: io::Read + io::Seek>(
+        reader: &mut R,
+        keys: impl Into<super::KeyChain>,
+    ) -> Result<bool, super::Error> {
+        let footer = super::PakFooter::from_reader(reader)?;
+        if !footer.encrypted {
+            return Ok(true);
+        }
+        let Some(key) = decryptor(&keys.into(), footer.encryption_guid)? else {
+            return Ok(false);
+        };
+        match read_index(reader, &footer, Some(&key)) {
+            Ok(_) => Ok(true),
+            Err(super::Error::WrongKey) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// registers a decompressor for the compression method name overriding any built in one
+    pub fn with_decompressor(
+        mut self,
+        name: &str,
+        decompressor: impl super::Decompressor + 'static,
+    ) -> Self {
+        self.add_decompressor(name, std::sync::Arc::new(decompressor));
+        self
+    }
+
+    pub(crate) fn add_decompressor(
+        &mut self,
+        name: &str,
+        decompressor: std::sync::Arc<dyn super::Decompressor>,
+    ) {
+        self.decompressors.insert(name, decompressor);
+    }
+
+    /// gets the compression methods the entries can use
+    pub fn compression(&self) -> &[Compression] {
+        &self.compression
     }
 
     pub fn version(&self) -> super::Version {
@@ -169,19 +340,117 @@ impl Pak {
         &self.mount_point
     }
 
+    /// gets the seed used to hash paths in the path hash index
+    pub fn path_hash_seed(&self) -> u64 {
+        self.path_hash_seed
+    }
+
+    /// hashes a path relative to the mount point the way the path hash index does
+    pub fn path_hash(&self, path: &str) -> u64 {
+        super::path_hash(path, self.path_hash_seed, self.version)
+    }
+
+    /// gets the path the pak was read from
+    pub fn path(&self) -> Option<&std::path::Path> {
+        self.path.as_deref()
+    }
+
     /// reads the entry into any writer
     pub fn read<W: io::Write>(&self, entry: &str, writer: &mut W) -> Result<(), super::Error> {
-        match self.entries.get(entry) {
-            Some(entry) => entry.read(
-                &self.path,
-                self.version,
-                &self.compression,
-                #[cfg(feature = "encryption")]
-                self.key.as_ref(),
-                writer,
-            ),
-            None => Err(super::Error::Missing(entry.to_string())),
+        self.read_entry(&mut self.source()?, self.mapped(), entry, writer)
+    }
+
+    /// reads the entry into any writer using the given reader as the pak source
+    pub fn read_from<R: io::Read + io::Seek, W: io::Write>(
+        &self,
+        reader: &mut R,
+        entry: &str,
+        writer: &mut W,
+    ) -> Result<(), super::Error> {
+        self.read_entry(reader, None, entry, writer)
+    }
+
+    /// reads the entry into any writer borrowing the data from the pak if it's in memory
+    pub(crate) fn read_entry<R: io::Read + io::Seek, W: io::Write>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+        entry: &str,
+        writer: &mut W,
+    ) -> Result<(), super::Error> {
+        self.live_entry(entry)?.read(
+            reader,
+            pak,
+            self.version,
+            &self.compression,
+            &self.decompressors,
+            #[cfg(feature = "encryption")]
+            self.key.as_ref(),
+            writer,
+        )
+    }
+
+    /// opens the entry as a reader which decompresses blocks on demand
+    pub fn open_entry(
+        &self,
+        entry: &str,
+    ) -> Result<super::EntryReader<'_, Source<'_>>, super::Error> {
+        self.open_entry_from(self.source()?, entry)
+    }
+
+    /// opens the entry as a reader which decompresses blocks on demand using the given reader as the pak source
+    pub fn open_entry_from<R: io::Read + io::Seek>(
+        &self,
+        reader: R,
+        entry: &str,
+    ) -> Result<super::EntryReader<'_, R>, super::Error> {
+        super::EntryReader::new(
+            reader,
+            self.live_entry(entry)?,
+            self.version,
+            &self.compression,
+            &self.decompressors,
+            #[cfg(feature = "encryption")]
+            self.key.as_ref(),
+        )
+    }
+
+    /// reads part of the entry into any writer - ranges past the end are cut short
+    pub fn read_range<W: io::Write>(
+        &self,
+        entry: &str,
+        range: std::ops::Range<u64>,
+        writer: &mut W,
+    ) -> Result<(), super::Error> {
+        self.read_range_from(&mut self.source()?, entry, range, writer)
+    }
+
+    /// reads part of the entry into any writer using the given reader as the pak source - ranges past the end are cut short
+    pub fn read_range_from<R: io::Read + io::Seek, W: io::Write>(
+        &self,
+        reader: &mut R,
+        entry: &str,
+        range: std::ops::Range<u64>,
+        writer: &mut W,
+    ) -> Result<(), super::Error> {
+        use io::Seek;
+        let mut entry = self.open_entry_from(reader, entry)?;
+        // only the blocks overlapping the range get decompressed
+        let mut remaining = range
+            .end
+            .saturating_sub(entry.seek(io::SeekFrom::Start(range.start))?);
+        while remaining != 0 {
+            let data = entry.fill()?;
+            if data.is_empty() {
+                break;
+            }
+            let len = data.len().min(remaining as usize);
+            writer.write_all(&data[..len])?;
+            entry.consume(len);
+            remaining -= len as u64;
         }
+        writer.flush()?;
+        Ok(())
     }
 
     /// reads the entry to the given path
@@ -190,17 +459,94 @@ impl Pak {
         entry: &str,
         path: impl AsRef<std::path::Path>,
     ) -> Result<(), super::Error> {
-        match self.entries.get(entry) {
-            Some(entry) => entry.read(
-                &self.path,
-                self.version,
-                &self.compression,
-                #[cfg(feature = "encryption")]
-                self.key.as_ref(),
-                &mut std::fs::File::create(path)?,
+        self.live_entry(entry)?;
+        self.read(entry, &mut std::fs::File::create(path)?)
+    }
+
+    /// checks the indices and every entry against their recorded hashes
+    pub fn verify(&self) -> Result<Vec<HashMismatch>, super::Error> {
+        self.verify_all(&mut self.source()?, self.mapped())
+    }
+
+    /// checks the indices and every entry against their recorded hashes using the given reader as the pak source
+    pub fn verify_from<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Vec<HashMismatch>, super::Error> {
+        self.verify_all(reader, None)
+    }
+
+    /// checks the indices and every entry borrowing the data from the pak if it's in memory
+    fn verify_all<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+    ) -> Result<Vec<HashMismatch>, super::Error> {
+        let mut mismatches = Vec::new();
+        for (block, mismatch) in [
+            (Some(&self.index), HashMismatch::Index),
+            (self.path_hash_index.as_ref(), HashMismatch::PathHashIndex),
+            (
+                self.full_dir_index.as_ref(),
+                HashMismatch::FullDirectoryIndex,
             ),
-            None => Err(super::Error::Missing(entry.to_string())),
+        ] {
+            if let Some(block) = block {
+                if !self.verify_block(reader, block)? {
+                    mismatches.push(mismatch);
+                }
+            }
+        }
+        for (name, _) in self.entries.iter().filter(|(_, entry)| !entry.is_deleted()) {
+            if !self.check_entry(reader, pak, name)? {
+                mismatches.push(HashMismatch::Entry(name.clone()));
+            }
         }
+        Ok(mismatches)
+    }
+
+    /// checks the stored data of the entry against its recorded hash
+    pub fn verify_entry(&self, entry: &str) -> Result<bool, super::Error> {
+        self.check_entry(&mut self.source()?, self.mapped(), entry)
+    }
+
+    /// checks the stored data of the entry against its recorded hash using the given reader as the pak source
+    pub fn verify_entry_from<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        entry: &str,
+    ) -> Result<bool, super::Error> {
+        self.check_entry(reader, None, entry)
+    }
+
+    /// checks the stored data of the entry borrowing it from the pak if it's in memory
+    fn check_entry<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+        entry: &str,
+    ) -> Result<bool, super::Error> {
+        self.live_entry(entry)?.verify(
+            reader,
+            pak,
+            self.version,
+            #[cfg(feature = "encryption")]
+            self.key.as_ref(),
+        )
+    }
+
+    fn verify_block<R: io::Read + io::Seek>(
+        &self,
+        reader: &mut R,
+        block: &IndexBlock,
+    ) -> Result<bool, super::Error> {
+        let data = block.read(
+            reader,
+            self.encrypted,
+            #[cfg(feature = "encryption")]
+            self.key.as_ref(),
+        )?;
+        Ok(super::hash::sha1(&data) == block.hash)
     }
 
     /// gets the entry as a vector of bytes
@@ -210,8 +556,474 @@ impl Pak {
         Ok(data)
     }
 
+    /// gets the entry borrowing it from the mapped pak if it's stored without compression or encryption
+    pub fn get_cow(&self, entry: &str) -> Result<std::borrow::Cow<'_, [u8]>, super::Error> {
+        #[cfg(feature = "mmap")]
+        if let Some(map) = &self.map {
+            return self.get_cow_from(map, entry);
+        }
+        self.get(entry).map(std::borrow::Cow::Owned)
+    }
+
+    /// gets the entry borrowing it from the pak in memory if it's stored without compression or encryption
+    pub fn get_cow_from<'a>(
+        &self,
+        pak: &'a [u8],
+        entry: &str,
+    ) -> Result<std::borrow::Cow<'a, [u8]>, super::Error> {
+        if let Some(data) = self.stored(pak, entry)? {
+            return Ok(std::borrow::Cow::Borrowed(data));
+        }
+        let mut data = Vec::new();
+        self.read_entry(&mut io::Cursor::new(pak), Some(pak), entry, &mut data)?;
+        Ok(std::borrow::Cow::Owned(data))
+    }
+
+    /// borrows the data of an entry stored without compression or encryption from the mapped pak
+    ///
+    /// gives none if the data has to be decompressed or decrypted
+    #[cfg(feature = "mmap")]
+    pub fn get_slice(&self, entry: &str) -> Result<Option<&[u8]>, super::Error> {
+        match &self.map {
+            Some(map) => self.stored(map, entry),
+            None => Ok(None),
+        }
+    }
+
+    /// slices the data of the entry out of the pak if it's stored as is
+    fn stored<'a>(&self, pak: &'a [u8], entry: &str) -> Result<Option<&'a [u8]>, super::Error> {
+        let Some(range) = self.live_entry(entry)?.stored_range(
+            &mut io::Cursor::new(pak),
+            self.version,
+            &self.compression,
+        )?
+        else {
+            return Ok(None);
+        };
+        match pak.get(range.start as usize..range.end as usize) {
+            Some(data) => Ok(Some(data)),
+            None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+
+    /// the mapped pak if there is one
+    pub(crate) fn mapped(&self) -> Option<&[u8]> {
+        #[cfg(feature = "mmap")]
+        return self.map.as_deref();
+        #[cfg(not(feature = "mmap"))]
+        None
+    }
+
+    /// opens the pak at its path or reads it from the mapping
+    pub(crate) fn source(&self) -> Result<Source<'_>, super::Error> {
+        #[cfg(feature = "mmap")]
+        if let Some(map) = &self.map {
+            return Ok(Source::Map(io::Cursor::new(map)));
+        }
+        match &self.path {
+            Some(path) => Ok(Source::File(std::fs::File::open(path)?)),
+            None => Err(super::Error::NoPath),
+        }
+    }
+
     /// gets the names of all entries
     pub fn entries(&self) -> Vec<String> {
-        self.entries.keys().cloned().collect::<Vec<String>>()
+        self.entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_deleted())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// gets the names of all entries this pak deletes from lower priority paks
+    pub fn deleted(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.is_deleted())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// gets the metadata of the entry
+    pub fn entry(&self, entry: &str) -> Result<super::EntryInfo, super::Error> {
+        match self.find(entry) {
+            Some(entry) => Ok(entry.info(&self.compression)),
+            None => Err(super::Error::Missing(entry.to_string())),
+        }
+    }
+
+    /// gets the path hashes and metadata of entries whose paths aren't known
+    pub fn hashed_entries(&self) -> Vec<(u64, super::EntryInfo)> {
+        self.path_hashes
+            .iter()
+            .filter(|(hash, _)| !self.named.contains(*hash))
+            .map(|(&hash, entry)| (hash, entry.info(&self.compression)))
+            .collect()
+    }
+
+    /// names entries from the path hash index using candidate paths and returns the hashes left unresolved
+    pub fn resolve_paths<S: AsRef<str>>(&mut self, paths: impl IntoIterator<Item = S>) -> Vec<u64> {
+        for path in paths {
+            let path = path.as_ref();
+            if self.entries.contains_key(path) {
+                continue;
+            }
+            let hash = self.path_hash(path);
+            if let Some(entry) = self.path_hashes.get(&hash) {
+                self.entries.insert(path.to_string(), entry.clone());
+                self.named.insert(hash);
+            }
+        }
+        self.hashed_entries()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect()
+    }
+
+    /// reads the entry with the path hash into any writer
+    pub fn read_hash<W: io::Write>(&self, hash: u64, writer: &mut W) -> Result<(), super::Error> {
+        self.read_hashed(&mut self.source()?, self.mapped(), hash, writer)
+    }
+
+    /// reads the entry with the path hash into any writer using the given reader as the pak source
+    pub fn read_hash_from<R: io::Read + io::Seek, W: io::Write>(
+        &self,
+        reader: &mut R,
+        hash: u64,
+        writer: &mut W,
+    ) -> Result<(), super::Error> {
+        self.read_hashed(reader, None, hash, writer)
+    }
+
+    /// reads the entry with the path hash borrowing the data from the pak if it's in memory
+    fn read_hashed<R: io::Read + io::Seek, W: io::Write>(
+        &self,
+        reader: &mut R,
+        pak: Option<&[u8]>,
+        hash: u64,
+        writer: &mut W,
+    ) -> Result<(), super::Error> {
+        match self.path_hashes.get(&hash) {
+            Some(entry) if entry.is_deleted() => Err(super::Error::Deleted(format!("{hash:016x}"))),
+            Some(entry) => entry.read(
+                reader,
+                pak,
+                self.version,
+                &self.compression,
+                &self.decompressors,
+                #[cfg(feature = "encryption")]
+                self.key.as_ref(),
+                writer,
+            ),
+            None => Err(super::Error::Missing(format!("{hash:016x}"))),
+        }
+    }
+
+    /// gets the names and metadata of all entries
+    pub fn entries_with_info(&self) -> Vec<(String, super::EntryInfo)> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_deleted())
+            .map(|(name, entry)| (name.clone(), entry.info(&self.compression)))
+            .collect()
+    }
+
+    /// gets the entry by path falling back to the path hash index
+    fn find(&self, entry: &str) -> Option<&super::entry::Entry> {
+        self.entries
+            .get(entry)
+            .or_else(|| self.path_hashes.get(&self.path_hash(entry)))
+    }
+
+    /// gets the entry if it exists and isn't a delete record
+    fn live_entry(&self, entry: &str) -> Result<&super::entry::Entry, super::Error> {
+        match self.find(entry) {
+            Some(found) if found.is_deleted() => Err(super::Error::Deleted(entry.to_string())),
+            Some(found) => Ok(found),
+            None => Err(super::Error::Missing(entry.to_string())),
+        }
+    }
+}
+
+/// where path based methods read the pak from
+pub enum Source<'a> {
+    /// the pak file reopened from its path
+    File(std::fs::File),
+    /// the mapping of the pak file
+    Map(io::Cursor<&'a [u8]>),
+}
+
+impl io::Read for Source<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::File(file) => file.read(buf),
+            Source::Map(map) => map.read(buf),
+        }
+    }
+}
+
+impl io::Seek for Source<'_> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match self {
+            Source::File(file) => file.seek(pos),
+            Source::Map(map) => map.seek(pos),
+        }
+    }
+}
+
+/// builds the decryptor for the key matching the encryption guid
+#[cfg(feature = "encryption")]
+fn decryptor(keys: &super::KeyChain, guid: u128) -> Result<Option<aes::Aes256Dec>, super::Error> {
+    use aes::cipher::KeyInit;
+    match keys.get(guid) {
+        Some(key) => match aes::Aes256Dec::new_from_slice(key) {
+            Ok(dec) => Ok(Some(dec)),
+            Err(_) => Err(super::Error::Aes),
+        },
+        None => Ok(None),
+    }
+}
+
+/// reads the primary index and decrypts it if needed
+fn read_index<R: io::Read + io::Seek>(
+    reader: &mut R,
+    footer: &super::PakFooter,
+    #[cfg(feature = "encryption")] key: Option<&aes::Aes256Dec>,
+) -> Result<Vec<u8>, super::Error> {
+    use super::ext::ReadExt;
+    reader.seek(io::SeekFrom::Start(footer.index_offset))?;
+    #[allow(unused_mut)]
+    let mut index = reader.read_len(footer.index_size as usize)?;
+    if footer.encrypted {
+        #[cfg(feature = "encryption")]
+        {
+            super::decrypt(key, &mut index)?;
+            // a wrong key still decrypts so make sure the result isn't garbage
+            if !decrypted(&index, &footer.index_hash) {
+                return Err(super::Error::WrongKey);
+            }
+        }
+        #[cfg(not(feature = "encryption"))]
+        return Err(super::Error::Encryption);
+    }
+    Ok(index)
+}
+
+/// whether the index looks like it was decrypted with the right key
+fn decrypted(index: &[u8], hash: &[u8; 20]) -> bool {
+    // older unrealpak versions don't always write the hash
+    if hash != &[0; 20] {
+        return &super::hash::sha1(index) == hash;
+    }
+    // the mount point comes first so garbage shows there
+    let Some(len) = index.get(..4) else {
+        return false;
+    };
+    let len = i32::from_le_bytes([len[0], len[1], len[2], len[3]]);
+    // an empty mount point is written without the null byte
+    if len == 0 {
+        return true;
+    }
+    // mount points are paths so won't be very long
+    if len.unsigned_abs() > 1024 {
+        return false;
+    }
+    let size = match len.is_negative() {
+        true => len.unsigned_abs() as usize * 2,
+        false => len as usize,
+    };
+    let Some(mount_point) = index.get(4..4 + size) else {
+        return false;
+    };
+    match len.is_negative() {
+        true => {
+            let chars: Vec<_> = mount_point
+                .chunks(2)
+                .map(|ch| u16::from_le_bytes([ch[0], ch[1]]))
+                .collect();
+            chars.last() == Some(&0)
+                && String::from_utf16(&chars[..chars.len() - 1])
+                    .is_ok_and(|mount_point| !mount_point.contains('\0'))
+        }
+        false => {
+            mount_point.last() == Some(&0)
+                && std::str::from_utf8(&mount_point[..size - 1])
+                    .is_ok_and(|mount_point| !mount_point.contains('\0'))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    #[test]
+    fn hashed_entries() -> Result<(), crate::Error> {
+        for full_directory_index in [true, false] {
+            let mut writer = crate::PakWriter::new(
+                io::Cursor::new(Vec::new()),
+                crate::Version::Fnv64BugFix,
+                "../../../Project/Content/",
+            )
+            .with_path_hash_seed(0x1234)
+            .with_full_directory_index(full_directory_index);
+            writer.write_entry("Maps/map.umap", b"map")?;
+            writer.write_entry("root.txt", b"root")?;
+            let data = writer.finish()?.into_inner();
+            let mut pak = super::Pak::from_reader_any(
+                &mut io::Cursor::new(data),
+                #[cfg(feature = "encryption")]
+                None,
+            )?;
+            if full_directory_index {
+                // every entry is named by the full directory index even when renamed
+                assert_eq!(pak.entries().len(), 2);
+                assert!(pak.hashed_entries().is_empty());
+                continue;
+            }
+            assert!(pak.entries().is_empty());
+            let map = pak.path_hash("Maps/map.umap");
+            let root = pak.path_hash("root.txt");
+            let mut hashes: Vec<_> = pak
+                .hashed_entries()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect();
+            hashes.sort();
+            let mut expected = vec![map, root];
+            expected.sort();
+            assert_eq!(hashes, expected);
+            // paths which aren't in the pak are ignored
+            assert_eq!(
+                pak.resolve_paths(["Maps/map.umap", "missing.txt"]),
+                vec![root]
+            );
+            assert_eq!(pak.entries(), vec!["Maps/map.umap".to_string()]);
+            assert_eq!(pak.entry("Maps/map.umap")?.uncompressed, 3);
+            assert!(pak.resolve_paths(["root.txt"]).is_empty());
+            assert_eq!(pak.entries().len(), 2);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn keys() -> Result<(), crate::Error> {
+        use crate::Version;
+        let key = [0x42; 32];
+        for version in [Version::IndexEncryption, Version::Fnv64BugFix] {
+            for mount_point in ["", "../../../Project/"] {
+                let mut writer =
+                    crate::PakWriter::new(io::Cursor::new(Vec::new()), version, mount_point)
+                        .with_key(key)?;
+                writer.write_entry("entry.txt", b"entry")?;
+                let mut data = writer.finish()?.into_inner();
+                let mut hashless = data.clone();
+                // older unrealpak versions leave the index hash empty
+                let hash = hashless.len() - version.footer_size() as usize
+                    + version.magic_offset() as usize
+                    + 24;
+                hashless[hash..hash + 20].fill(0);
+                for data in [&mut data, &mut hashless] {
+                    let read = |keys: Option<&[u8]>| {
+                        super::Pak::from_reader_any(&mut io::Cursor::new(&data), keys)
+                    };
+                    let test = |keys: Option<&[u8]>| {
+                        super::Pak::test_key_from(&mut io::Cursor::new(&data), keys)
+                    };
+                    let pak = read(Some(&key))?;
+                    assert_eq!(pak.mount_point(), mount_point);
+                    let mut entry = Vec::new();
+                    pak.read_from(
+                        &mut io::Cursor::new(&data),
+                        &crate::ext::name("entry.txt".to_string()),
+                        &mut entry,
+                    )?;
+                    assert_eq!(entry, b"entry");
+                    assert!(test(Some(&key))?);
+                    assert!(matches!(
+                        read(Some(&[0x24; 32])),
+                        Err(crate::Error::WrongKey)
+                    ));
+                    assert!(!test(Some(&[0x24; 32]))?);
+                    assert!(matches!(read(None), Err(crate::Error::Encrypted)));
+                    assert!(!test(None)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn open_mapped_entry() -> Result<(), crate::Error> {
+        use io::Read;
+        let mut writer =
+            crate::PakWriter::new(io::Cursor::new(Vec::new()), crate::Version::Fnv64BugFix, "");
+        writer.write_entry("entry.txt", b"entry")?;
+        let path = std::env::temp_dir().join(format!("unpak-mapped-{}.pak", std::process::id()));
+        std::fs::write(&path, writer.finish()?.into_inner())?;
+        let pak = super::Pak::new_any(
+            &path,
+            #[cfg(feature = "encryption")]
+            None,
+        )?;
+        // the mapping outlives the file so reopening it would fail
+        std::fs::remove_file(&path)?;
+        let mut data = Vec::new();
+        pak.open_entry(&crate::ext::name("entry.txt".to_string()))?
+            .read_to_end(&mut data)?;
+        assert_eq!(data, b"entry");
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn read_mapped() -> Result<(), crate::Error> {
+        let large: Vec<u8> = (0..0x30000u32).map(|i| (i % 251) as u8).collect();
+        #[cfg(feature = "encryption")]
+        let key = [0x42; 32];
+        for encrypted in [false, true] {
+            #[allow(unused_mut)]
+            let mut writer =
+                crate::PakWriter::new(io::Cursor::new(Vec::new()), crate::Version::Fnv64BugFix, "");
+            #[cfg(feature = "compression")]
+            {
+                writer = writer.with_compression(crate::Compression::Zlib);
+            }
+            #[cfg(feature = "encryption")]
+            if encrypted {
+                writer = writer.with_key(key)?;
+            }
+            #[cfg(not(feature = "encryption"))]
+            if encrypted {
+                continue;
+            }
+            writer.write_entry("large.bin", &large)?;
+            writer.write_entry("small.txt", b"small")?;
+            let path = std::env::temp_dir().join(format!(
+                "unpak-read-mapped-{}-{encrypted}.pak",
+                std::process::id()
+            ));
+            std::fs::write(&path, writer.finish()?.into_inner())?;
+            let pak = super::Pak::new_any(
+                &path,
+                #[cfg(feature = "encryption")]
+                encrypted.then_some(&key[..]),
+            )?;
+            // everything has to come from the mapping once the file is gone
+            std::fs::remove_file(&path)?;
+            let name = crate::ext::name("large.bin".to_string());
+            assert_eq!(pak.get(&name)?, large);
+            assert_eq!(
+                &*pak.get_cow(&crate::ext::name("small.txt".to_string()))?,
+                b"small"
+            );
+            assert!(pak.verify()?.is_empty());
+            let mut data = Vec::new();
+            pak.read_hash(pak.path_hash("large.bin"), &mut data)?;
+            assert_eq!(data, large);
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,225 @@
+use super::Pak;
+use std::io;
+
+/// multiple paks merged into one filesystem with patch paks taking priority
+#[derive(Debug)]
+pub struct PakSet {
+    // sorted from lowest to highest priority
+    paks: Vec<Pak>,
+    // full path to the winning pak and its entry name
+    entries: hashbrown::HashMap<String, (usize, String)>,
+}
+
+impl PakSet {
+    /// reads every pak in the directory with guessed versions
+    pub fn new(
+        dir: impl AsRef<std::path::Path>,
+        #[cfg(feature = "encryption")] keys: impl Into<super::KeyChain>,
+    ) -> Result<Self, super::Error> {
+        #[cfg(feature = "encryption")]
+        let keys = keys.into();
+        let mut paks = Vec::new();
+        for file in std::fs::read_dir(dir)? {
+            let path = file?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("pak"))
+            {
+                paks.push(Pak::new_any(
+                    path,
+                    #[cfg(feature = "encryption")]
+                    &keys,
+                )?);
+            }
+        }
+        // read_dir isn't guaranteed to be in any order
+        paks.sort_by(|a, b| a.path().cmp(&b.path()));
+        Ok(Self::from_paks(paks))
+    }
+
+    /// merges already read paks - paks of equal priority are overridden by those after them
+    pub fn from_paks(paks: impl IntoIterator<Item = Pak>) -> Self {
+        let mut paks: Vec<_> = paks.into_iter().collect();
+        // stable so the given order breaks ties
+        paks.sort_by_key(|pak| pak.path().map_or(0, order));
+        let mut entries = hashbrown::HashMap::new();
+        for (i, pak) in paks.iter().enumerate() {
+            let mount_point = mount_point(pak.mount_point());
+            for entry in pak.entries() {
+                entries.insert(mount_point.clone() + &entry, (i, entry));
+            }
+            // delete records remove the entry from lower priority paks
+            for entry in pak.deleted() {
+                entries.remove(&(mount_point.clone() + &entry));
+            }
+        }
+        Self { paks, entries }
+    }
+
+    /// registers a decompressor for the compression method name on every pak
+    pub fn with_decompressor(
+        mut self,
+        name: &str,
+        decompressor: impl super::Decompressor + 'static,
+    ) -> Self {
+        let decompressor = std::sync::Arc::new(decompressor);
+        for pak in self.paks.iter_mut() {
+            pak.add_decompressor(name, decompressor.clone());
+        }
+        self
+    }
+
+    /// gets the paks from lowest to highest priority
+    pub fn paks(&self) -> &[Pak] {
+        &self.paks
+    }
+
+    /// gets the pak the path resolves to and the name of the entry within it
+    pub fn resolve(&self, path: &str) -> Option<(&Pak, &str)> {
+        self.entries
+            .get(path)
+            .map(|(i, entry)| (&self.paks[*i], entry.as_str()))
+    }
+
+    /// reads the entry into any writer
+    pub fn read<W: io::Write>(&self, entry: &str, writer: &mut W) -> Result<(), super::Error> {
+        match self.resolve(entry) {
+            Some((pak, entry)) => pak.read(entry, writer),
+            None => Err(super::Error::Missing(entry.to_string())),
+        }
+    }
+
+    /// reads the entry to the given path
+    pub fn read_to_file(
+        &self,
+        entry: &str,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), super::Error> {
+        match self.resolve(entry) {
+            Some((pak, entry)) => pak.read_to_file(entry, path),
+            None => Err(super::Error::Missing(entry.to_string())),
+        }
+    }
+
+    /// gets the entry as a vector of bytes
+    pub fn get(&self, entry: &str) -> Result<Vec<u8>, super::Error> {
+        let mut data = Vec::new();
+        self.read(entry, &mut data)?;
+        Ok(data)
+    }
+
+    /// gets the entry borrowing it from the mapped pak if it's stored without compression or encryption
+    pub fn get_cow(&self, entry: &str) -> Result<std::borrow::Cow<'_, [u8]>, super::Error> {
+        match self.resolve(entry) {
+            Some((pak, entry)) => pak.get_cow(entry),
+            None => Err(super::Error::Missing(entry.to_string())),
+        }
+    }
+
+    /// gets the paths of all entries including the mount point
+    pub fn entries(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect::<Vec<String>>()
+    }
+}
+
+/// strips the relative part of the mount point so paths from different paks line up
+pub(crate) fn mount_point(mount_point: &str) -> String {
+    match mount_point.trim_start_matches("../").trim_matches('/') {
+        "" => String::new(),
+        mount_point => mount_point.to_string() + "/",
+    }
+}
+
+/// the mount order unreal gives a pak from its file name
+fn order(path: &std::path::Path) -> u32 {
+    let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return 0;
+    };
+    let Some(stem) = stem.strip_suffix("_P") else {
+        return 0;
+    };
+    // patches can be versioned like pakchunk0-Windows_2_P with the first patch being version 1
+    let version = match stem.rsplit_once('_') {
+        Some((_, version)) => match version.parse::<u32>() {
+            Ok(version) if version >= 1 => version + 1,
+            _ => 1,
+        },
+        None => 1,
+    };
+    100 * version
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn patch_order() -> Result<(), crate::Error> {
+        let dir = std::env::temp_dir().join(format!("unpak-set-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        // alphabetically the second patch comes before the first
+        for (name, version) in [
+            ("pakchunk0-Windows.pak", "base"),
+            ("pakchunk0-Windows_2_P.pak", "patch2"),
+            ("pakchunk0-Windows_P.pak", "patch1"),
+        ] {
+            let mut writer = crate::PakWriter::new(
+                std::io::Cursor::new(Vec::new()),
+                crate::Version::Fnv64BugFix,
+                "../../../Project/",
+            );
+            match version {
+                "base" => {
+                    for entry in ["a.txt", "b.txt", "c.txt"] {
+                        writer.write_entry(entry, version.as_bytes())?;
+                    }
+                }
+                "patch1" => {
+                    writer.write_entry("a.txt", version.as_bytes())?;
+                    writer.delete_entry("b.txt")?;
+                    writer.write_entry("c.txt", version.as_bytes())?;
+                }
+                _ => writer.write_entry("a.txt", version.as_bytes())?,
+            }
+            std::fs::write(dir.join(name), writer.finish()?.into_inner())?;
+        }
+        let set = super::PakSet::new(
+            &dir,
+            #[cfg(feature = "encryption")]
+            None,
+        )?;
+        let names: Vec<_> = set
+            .paks()
+            .iter()
+            .filter_map(|pak| pak.path()?.file_name()?.to_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "pakchunk0-Windows.pak",
+                "pakchunk0-Windows_P.pak",
+                "pakchunk0-Windows_2_P.pak"
+            ]
+        );
+        let path = |entry: &str| "Project/".to_string() + &crate::ext::name(entry.to_string());
+        assert_eq!(set.get(&path("a.txt"))?, b"patch2");
+        assert_eq!(set.get(&path("c.txt"))?, b"patch1");
+        // the first patch deletes the entry from the base pak
+        assert!(matches!(
+            set.get(&path("b.txt")),
+            Err(crate::Error::Missing(_))
+        ));
+        assert_eq!(set.entries().len(), 2);
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn order() {
+        let order = |name: &str| super::order(std::path::Path::new(name));
+        assert_eq!(order("pakchunk0-Windows.pak"), 0);
+        assert!(order("pakchunk0-Windows_P.pak") > order("pakchunk0-Windows.pak"));
+        assert!(order("pakchunk0-Windows_2_P.pak") > order("pakchunk0-Windows_P.pak"));
+        assert!(order("pakchunk0-Windows_10_P.pak") > order("pakchunk0-Windows_2_P.pak"));
+        // a version which isn't a number is the first patch
+        assert_eq!(order("pakchunk0-Windows_P.pak"), order("mod_Windows_P.pak"));
+    }
+}
@@ -0,0 +1,130 @@
+use super::{decompressor::Decompressors, entry::Entry, Compression, Version};
+use std::{io, ops::Range};
+
+/// reads an entry, decrypting and decompressing blocks as they're needed
+pub struct EntryReader<'a, R: io::Read + io::Seek> {
+    reader: R,
+    entry: &'a Entry,
+    compression: Option<&'a Compression>,
+    decompressors: &'a Decompressors,
+    #[cfg(feature = "encryption")]
+    key: Option<&'a aes::Aes256Dec>,
+    data_offset: u64,
+    blocks: Vec<(Range<usize>, Range<u64>)>,
+    size: u64,
+    pos: u64,
+    // the index and data of the last block read
+    block: Option<(usize, Vec<u8>)>,
+}
+
+impl<'a, R: io::Read + io::Seek> EntryReader<'a, R> {
+    pub(crate) fn new(
+        mut reader: R,
+        entry: &'a Entry,
+        version: Version,
+        compression: &'a [Compression],
+        decompressors: &'a Decompressors,
+        #[cfg(feature = "encryption")] key: Option<&'a aes::Aes256Dec>,
+    ) -> Result<Self, super::Error> {
+        let data_offset = entry.data_offset(&mut reader, version)?;
+        let compression = entry.compression(compression);
+        let blocks = entry.block_ranges(version, data_offset, compression);
+        Ok(Self {
+            reader,
+            entry,
+            compression,
+            decompressors,
+            #[cfg(feature = "encryption")]
+            key,
+            data_offset,
+            size: blocks.last().map_or(0, |(_, block)| block.end),
+            blocks,
+            pos: 0,
+            block: None,
+        })
+    }
+
+    /// the decompressed size of the entry
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// whether the entry is empty
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// decompresses the block at the current position and gets the rest of it
+    pub(crate) fn fill(&mut self) -> Result<&[u8], super::Error> {
+        if self.pos >= self.size {
+            return Ok(&[]);
+        }
+        let i = self
+            .blocks
+            .partition_point(|(_, block)| block.end <= self.pos);
+        let (_, data) = match self.block.take() {
+            Some(block) if block.0 == i => self.block.insert(block),
+            _ => {
+                let data = self.entry.read_block(
+                    &mut self.reader,
+                    self.data_offset,
+                    self.blocks[i].clone(),
+                    self.compression,
+                    self.decompressors,
+                    #[cfg(feature = "encryption")]
+                    self.key,
+                )?;
+                self.block.insert((i, data))
+            }
+        };
+        match data.get((self.pos - self.blocks[i].1.start) as usize..) {
+            Some(rest) if !rest.is_empty() => Ok(rest),
+            _ => Err(super::Error::Other(
+                "block decompressed to less than its recorded size".to_string(),
+            )),
+        }
+    }
+
+    /// skips the given number of bytes without decompressing them
+    pub(crate) fn consume(&mut self, len: usize) {
+        self.pos += len as u64;
+    }
+
+    /// gives back the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read + io::Seek> io::Read for EntryReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.fill().map_err(|e| match e {
+            super::Error::Io(e) => e,
+            e => io::Error::other(e),
+        })?;
+        let len = buf.len().min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: io::Read + io::Seek> io::Seek for EntryReader<'_, R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            io::SeekFrom::Start(pos) => Some(pos),
+            io::SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            io::SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        match pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use super::{ext::WriteExt, Compression, Version};
+use byteorder::{WriteBytesExt, LE};
+use std::io;
+
+/// writes a new pak entry by entry
+pub struct PakWriter<W: io::Write + io::Seek> {
+    writer: W,
+    version: Version,
+    mount_point: String,
+    path_hash_seed: u64,
+    compression: Compression,
+    block_size: u32,
+    #[cfg(feature = "encryption")]
+    key: Option<aes::Aes256Enc>,
+    encryption_guid: u128,
+    full_directory_index: bool,
+    entries: Vec<(String, super::entry::Entry)>,
+}
+
+impl<W: io::Write + io::Seek> PakWriter<W> {
+    /// starts writing a pak with a version and mount point
+    pub fn new(writer: W, version: Version, mount_point: impl Into<String>) -> Self {
+        Self {
+            writer,
+            version,
+            mount_point: mount_point.into(),
+            path_hash_seed: 0,
+            compression: Compression::None,
+            // the default block size of unrealpak
+            block_size: 0x10000,
+            #[cfg(feature = "encryption")]
+            key: None,
+            encryption_guid: 0,
+            full_directory_index: true,
+            entries: Vec::new(),
+        }
+    }
+
+    /// sets the seed used to hash paths in the path hash index
+    pub fn with_path_hash_seed(mut self, seed: u64) -> Self {
+        self.path_hash_seed = seed;
+        self
+    }
+
+    /// sets the compression used for entries
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// sets the uncompressed size of each compression block
+    pub fn with_block_size(mut self, block_size: u32) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// sets the key used to encrypt entries and the index
+    #[cfg(feature = "encryption")]
+    pub fn with_key(mut self, key: impl AsRef<[u8]>) -> Result<Self, super::Error> {
+        if self.version < Version::CompressionEncryption {
+            return Err(super::Error::Other(format!(
+                "v{} paks don't support encryption",
+                self.version.as_u32()
+            )));
+        }
+        use aes::cipher::KeyInit;
+        let Ok(enc) = aes::Aes256Enc::new_from_slice(key.as_ref()) else {
+            return Err(super::Error::Aes);
+        };
+        self.key = Some(enc);
+        Ok(self)
+    }
+
+    /// sets the guid of the encryption key written to the footer
+    pub fn with_encryption_guid(mut self, guid: u128) -> Self {
+        self.encryption_guid = guid;
+        self
+    }
+
+    /// sets whether v10+ paks get a full directory index - without one paths are only known by their hash
+    pub fn with_full_directory_index(mut self, full_directory_index: bool) -> Self {
+        self.full_directory_index = full_directory_index;
+        self
+    }
+
+    /// writes an entry at a path relative to the mount point
+    pub fn write_entry(
+        &mut self,
+        path: impl Into<String>,
+        data: &[u8],
+    ) -> Result<(), super::Error> {
+        let slot = match &self.compression {
+            Compression::None => None,
+            // older versions have a fixed compression table
+            compression if self.version < Version::FNameBasedCompression => {
+                match [Compression::Zlib, Compression::Gzip, Compression::Oodle]
+                    .iter()
+                    .position(|slot| slot == compression)
+                {
+                    Some(slot) => Some(slot),
+                    None => {
+                        return Err(super::Error::Other(format!(
+                            "v{} paks don't support {compression} compression",
+                            self.version.as_u32()
+                        )))
+                    }
+                }
+            }
+            _ => Some(0),
+        };
+        let entry = super::entry::Entry::write_data(
+            &mut self.writer,
+            self.version,
+            slot.map(|slot| (slot, &self.compression)),
+            self.block_size,
+            #[cfg(feature = "encryption")]
+            self.key.as_ref(),
+            data,
+        )?;
+        self.entries.push((path.into(), entry));
+        Ok(())
+    }
+
+    /// writes a delete record removing the entry from lower priority paks
+    pub fn delete_entry(&mut self, path: impl Into<String>) -> Result<(), super::Error> {
+        if self.version < Version::DeleteRecords {
+            return Err(super::Error::Other(format!(
+                "v{} paks don't support delete records",
+                self.version.as_u32()
+            )));
+        }
+        self.entries
+            .push((path.into(), super::entry::Entry::delete_record()));
+        Ok(())
+    }
+
+    /// writes an entry from any reader at a path relative to the mount point
+    pub fn write_entry_from<R: io::Read>(
+        &mut self,
+        path: impl Into<String>,
+        reader: &mut R,
+    ) -> Result<(), super::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        self.write_entry(path, &data)
+    }
+
+    /// writes the index and footer then gives back the writer
+    pub fn finish(mut self) -> Result<W, super::Error> {
+        let index_offset = self.writer.stream_position()?;
+        let (index, hash) = match self.version >= Version::PathHashIndex {
+            true => self.write_path_hash_index(index_offset)?,
+            false => {
+                let mut index = Vec::new();
+                index.write_string(&self.mount_point)?;
+                index.write_u32::<LE>(self.entries.len() as u32)?;
+                for (path, entry) in self.entries.iter() {
+                    index.write_string(path)?;
+                    entry.write(&mut index, self.version)?;
+                }
+                let (index, hash) = self.seal(index);
+                self.writer.write_all(&index)?;
+                (index, hash)
+            }
+        };
+        super::footer::PakFooter {
+            version: self.version,
+            encryption_guid: self.encryption_guid,
+            encrypted: self.index_encrypted(),
+            index_offset,
+            index_size: index.len() as u64,
+            index_hash: hash,
+            frozen: false,
+            compression: match &self.compression {
+                Compression::None => Vec::new(),
+                compression => vec![compression.clone()],
+            },
+        }
+        .write(&mut self.writer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// writes the primary, path hash and full directory indices and returns the primary with its hash
+    fn write_path_hash_index(
+        &mut self,
+        index_offset: u64,
+    ) -> Result<(Vec<u8>, [u8; 20]), super::Error> {
+        use io::Write;
+        let mut encoded = Vec::new();
+        let mut unencoded = Vec::new();
+        let mut locations = Vec::with_capacity(self.entries.len());
+        for (_, entry) in self.entries.iter() {
+            match entry.encodable() {
+                true => {
+                    locations.push(encoded.len() as i32);
+                    entry.write_encoded(&mut encoded)?;
+                }
+                // negative locations are indices into the unencoded records
+                false => {
+                    locations.push(-(unencoded.len() as i32) - 1);
+                    unencoded.push(entry);
+                }
+            }
+        }
+        let mut path_hash = Vec::new();
+        path_hash.write_u32::<LE>(self.entries.len() as u32)?;
+        for ((path, _), &location) in self.entries.iter().zip(locations.iter()) {
+            path_hash.write_u64::<LE>(super::hash::path_hash(
+                path,
+                self.path_hash_seed,
+                self.version,
+            ))?;
+            path_hash.write_i32::<LE>(location)?;
+        }
+        // pruned directory index
+        path_hash.write_u32::<LE>(0)?;
+        // unreal lists every directory including the parents of those with files
+        let mut dirs = std::collections::BTreeMap::<&str, Vec<(&str, i32)>>::new();
+        dirs.insert("/", Vec::new());
+        for ((path, _), &location) in self.entries.iter().zip(locations.iter()) {
+            let (dir, file) = match path.rfind('/') {
+                Some(i) => path.split_at(i + 1),
+                None => ("/", path.as_str()),
+            };
+            for (i, _) in dir.match_indices('/') {
+                dirs.entry(&dir[..=i]).or_default();
+            }
+            dirs.entry(dir).or_default().push((file, location));
+        }
+        let (path_hash, path_hash_hash) = self.seal(path_hash);
+        let mut full_dir = Vec::new();
+        full_dir.write_u32::<LE>(dirs.len() as u32)?;
+        for (dir, files) in dirs {
+            full_dir.write_string(dir)?;
+            full_dir.write_u32::<LE>(files.len() as u32)?;
+            for (file, location) in files {
+                full_dir.write_string(file)?;
+                full_dir.write_i32::<LE>(location)?;
+            }
+        }
+        let (full_dir, full_dir_hash) = self.seal(full_dir);
+        let primary = |path_hash_offset: u64, full_dir_offset: u64| -> Result<_, super::Error> {
+            let mut index = Vec::new();
+            index.write_string(&self.mount_point)?;
+            index.write_u32::<LE>(self.entries.len() as u32)?;
+            index.write_u64::<LE>(self.path_hash_seed)?;
+            // path hash index
+            index.write_u32::<LE>(1)?;
+            index.write_u64::<LE>(path_hash_offset)?;
+            index.write_u64::<LE>(path_hash.len() as u64)?;
+            index.write_all(&path_hash_hash)?;
+            // full directory index
+            index.write_u32::<LE>(self.full_directory_index as u32)?;
+            if self.full_directory_index {
+                index.write_u64::<LE>(full_dir_offset)?;
+                index.write_u64::<LE>(full_dir.len() as u64)?;
+                index.write_all(&full_dir_hash)?;
+            }
+            index.write_u32::<LE>(encoded.len() as u32)?;
+            index.write_all(&encoded)?;
+            index.write_u32::<LE>(unencoded.len() as u32)?;
+            for entry in unencoded.iter() {
+                entry.write(&mut index, self.version)?;
+            }
+            Ok(self.seal(index))
+        };
+        // the size of the primary index doesn't depend on the offsets
+        let path_hash_offset = index_offset + primary(0, 0)?.0.len() as u64;
+        let full_dir_offset = path_hash_offset + path_hash.len() as u64;
+        let (index, hash) = primary(path_hash_offset, full_dir_offset)?;
+        self.writer.write_all(&index)?;
+        self.writer.write_all(&path_hash)?;
+        if self.full_directory_index {
+            self.writer.write_all(&full_dir)?;
+        }
+        Ok((index, hash))
+    }
+
+    fn index_encrypted(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.key.is_some() && self.version >= Version::IndexEncryption;
+        #[cfg(not(feature = "encryption"))]
+        false
+    }
+
+    /// hashes then encrypts index data if the index is encrypted
+    #[allow(unused_mut)]
+    fn seal(&self, mut index: Vec<u8>) -> (Vec<u8>, [u8; 20]) {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = self.key.as_ref().filter(|_| self.index_encrypted()) {
+            // the hash includes the padding
+            index.resize((index.len() + 15) & !15, 0);
+            let hash = super::hash::sha1(&index);
+            super::encrypt(key, &mut index);
+            return (index, hash);
+        }
+        let hash = super::hash::sha1(&index);
+        (index, hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compression, PakWriter, Version};
+    use std::io;
+
+    /// writes a pak to a temporary file and checks it reads back the same
+    fn round_trip(
+        version: Version,
+        compression: Compression,
+        key: Option<&[u8]>,
+    ) -> Result<(), crate::Error> {
+        // spans multiple blocks and compresses well
+        let large: Vec<u8> = (0..0x30000u32).map(|i| (i % 251) as u8).collect();
+        let files: [(&str, &[u8]); 3] = [
+            ("Content/large.bin", &large),
+            ("small.txt", b"small"),
+            ("empty", b""),
+        ];
+        let mut writer = PakWriter::new(io::Cursor::new(Vec::new()), version, "../../../Project/")
+            .with_compression(compression.clone())
+            .with_block_size(0x10000);
+        #[cfg(feature = "encryption")]
+        if let Some(key) = key {
+            writer = writer.with_key(key)?;
+        }
+        for (path, data) in files {
+            writer.write_entry(path, data)?;
+        }
+        let path = std::env::temp_dir().join(format!(
+            "unpak-{}-{version}-{compression}-{}.pak",
+            std::process::id(),
+            key.is_some()
+        ));
+        std::fs::write(&path, writer.finish()?.into_inner())?;
+        let read = || -> Result<(), crate::Error> {
+            let pak = crate::Pak::new(
+                &path,
+                version,
+                #[cfg(feature = "encryption")]
+                key,
+            )?;
+            assert_eq!(pak.entries().len(), files.len());
+            for (path, data) in files {
+                let name = crate::ext::name(path.to_string());
+                assert_eq!(pak.get(&name)?, data, "{name} in {version} {compression}");
+                assert!(
+                    pak.verify_entry(&name)?,
+                    "{name} in {version} {compression}"
+                );
+            }
+            Ok(())
+        };
+        let result = read();
+        std::fs::remove_file(&path)?;
+        result
+    }
+
+    #[test]
+    fn round_trips() -> Result<(), crate::Error> {
+        let compressions = [
+            Compression::None,
+            #[cfg(feature = "compression")]
+            Compression::Zlib,
+            #[cfg(feature = "compression")]
+            Compression::Gzip,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+            #[cfg(feature = "lz4")]
+            Compression::Lz4,
+        ];
+        for version in Version::iter() {
+            for compression in compressions.iter() {
+                // only named compression slots can hold these
+                if matches!(compression, Compression::Zstd | Compression::Lz4)
+                    && version < Version::FNameBasedCompression
+                {
+                    continue;
+                }
+                round_trip(version, compression.clone(), None)?;
+                #[cfg(feature = "encryption")]
+                if version >= Version::CompressionEncryption {
+                    round_trip(version, compression.clone(), Some(&[0x42; 32]))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// writes an entry spanning several blocks to memory and reads the pak back
+    #[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+    fn blocks(
+        version: Version,
+        key: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, crate::Pak, Vec<u8>), crate::Error> {
+        // the last block is only partly filled
+        let large: Vec<u8> = (0..0x30123u32).map(|i| (i % 251) as u8).collect();
+        #[allow(unused_mut)]
+        let mut writer =
+            PakWriter::new(io::Cursor::new(Vec::new()), version, "").with_block_size(0x10000);
+        #[cfg(feature = "compression")]
+        {
+            writer = writer.with_compression(Compression::Zlib);
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(key) = key {
+            writer = writer.with_key(key)?;
+        }
+        writer.write_entry("large.bin", &large)?;
+        let data = writer.finish()?.into_inner();
+        let pak = crate::Pak::from_reader(
+            &mut io::Cursor::new(&data),
+            version,
+            #[cfg(feature = "encryption")]
+            key,
+        )?;
+        Ok((data, pak, large))
+    }
+
+    /// the versions with different block offsets and the keys they can be read with
+    fn block_cases() -> Vec<(Version, Option<&'static [u8]>)> {
+        let mut cases = Vec::new();
+        for version in [
+            Version::CompressionEncryption,
+            Version::RelativeChunkOffsets,
+            Version::Fnv64BugFix,
+        ] {
+            cases.push((version, None));
+            #[cfg(feature = "encryption")]
+            cases.push((version, Some(&[0x42; 32][..])));
+        }
+        cases
+    }
+
+    #[test]
+    fn seek_entry() -> Result<(), crate::Error> {
+        use io::{Read, Seek};
+        for (version, key) in block_cases() {
+            let (data, pak, large) = blocks(version, key)?;
+            let name = crate::ext::name("large.bin".to_string());
+            let mut entry = pak.open_entry_from(io::Cursor::new(&data), &name)?;
+            assert_eq!(entry.len(), large.len() as u64);
+            // from the middle of one block into the next
+            let mut buf = vec![0; 0x10000];
+            assert_eq!(entry.seek(io::SeekFrom::Start(0x18000))?, 0x18000);
+            entry.read_exact(&mut buf)?;
+            assert_eq!(buf, large[0x18000..0x28000]);
+            // backwards into an earlier block
+            assert_eq!(entry.seek(io::SeekFrom::Current(-0x20000))?, 0x8000);
+            entry.read_exact(&mut buf[..0x100])?;
+            assert_eq!(buf[..0x100], large[0x8000..0x8100]);
+            // the partly filled last block
+            entry.seek(io::SeekFrom::End(-0x200))?;
+            let mut end = Vec::new();
+            entry.read_to_end(&mut end)?;
+            assert_eq!(end, large[large.len() - 0x200..]);
+            // past the end reads nothing
+            entry.seek(io::SeekFrom::End(0x10))?;
+            assert_eq!(entry.read(&mut buf)?, 0);
+            assert!(entry.seek(io::SeekFrom::Current(-0x40000)).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_ranges() -> Result<(), crate::Error> {
+        for (version, key) in block_cases() {
+            let (data, pak, large) = blocks(version, key)?;
+            let name = crate::ext::name("large.bin".to_string());
+            let len = large.len() as u64;
+            for (range, expected) in [
+                // within a single block
+                (0x100..0x200, 0x100..0x200),
+                // across a block boundary
+                (0xfff0..0x10010, 0xfff0..0x10010),
+                // across every block
+                (0x10..len - 0x10, 0x10..len - 0x10),
+                // past the end is cut short
+                (0x2fff0..0x40000, 0x2fff0..len),
+                (len..len + 0x10, len..len),
+                (0x50000..0x60000, len..len),
+            ] {
+                let mut buf = Vec::new();
+                pak.read_range_from(&mut io::Cursor::new(&data), &name, range.clone(), &mut buf)?;
+                assert_eq!(
+                    buf,
+                    large[expected.start as usize..expected.end as usize],
+                    "{range:?} in {version}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_compression_slots() {
+        for compression in [
+            Compression::Zstd,
+            Compression::Lz4,
+            Compression::Other("custom".to_string()),
+        ] {
+            let mut writer =
+                PakWriter::new(io::Cursor::new(Vec::new()), Version::EncryptionKeyUuid, "")
+                    .with_compression(compression);
+            assert!(writer.write_entry("entry", b"data").is_err());
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd() -> Result<(), crate::Error> {
+        round_trip(Version::Fnv64BugFix, Compression::Zstd, None)
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4() -> Result<(), crate::Error> {
+        round_trip(Version::Fnv64BugFix, Compression::Lz4, None)
+    }
+}